@@ -3,7 +3,7 @@
 
 use crate::{
     ast::stmt::Stmt,
-    token::{Literal, Token},
+    token::{Literal, Span, Token},
 };
 use std::{fmt, rc::Rc};
 
@@ -60,13 +60,34 @@ pub enum Expr<'source> {
     Lambda {
         params: Vec<Token<'source>>,
         body: Vec<Stmt<'source>>,
+        span: Span,
     },
-    Literal(Literal),
+    Literal(Literal, Span),
     Get {
         object: Rc<Expr<'source>>,
         name: Token<'source>,
     },
-    Grouping(Rc<Expr<'source>>),
+    Grouping(Rc<Expr<'source>>, Span), // the span includes the parentheses
+    List {
+        elements: Vec<Rc<Expr<'source>>>,
+        span: Span, // from '[' to ']'
+    },
+    Map {
+        brace: Token<'source>,                                // the opening '{'
+        entries: Vec<(Rc<Expr<'source>>, Rc<Expr<'source>>)>, // key, value
+        span: Span,                                           // from '{' to '}'
+    },
+    Index {
+        object: Rc<Expr<'source>>,
+        index: Rc<Expr<'source>>,
+        bracket: Token<'source>, // the closing ']'
+    },
+    IndexSet {
+        object: Rc<Expr<'source>>,
+        index: Rc<Expr<'source>>,
+        bracket: Token<'source>,
+        value: Rc<Expr<'source>>,
+    },
 }
 
 impl<'source> Expr<'source> {
@@ -152,8 +173,8 @@ impl<'source> Expr<'source> {
         }
     }
 
-    pub fn literal(val: Literal) -> Self {
-        Expr::Literal(val)
+    pub fn literal(val: Literal, span: Span) -> Self {
+        Expr::Literal(val, span)
     }
 
     pub fn get(object: Expr<'source>, name: Token<'source>) -> Self {
@@ -163,14 +184,49 @@ impl<'source> Expr<'source> {
         }
     }
 
-    pub fn grouping(expr: Expr<'source>) -> Self {
-        Expr::Grouping(Rc::new(expr))
+    pub fn grouping(expr: Expr<'source>, span: Span) -> Self {
+        Expr::Grouping(Rc::new(expr), span)
     }
 
-    pub fn lambda(paramaters: Vec<Token<'source>>, bod: Vec<Stmt<'source>>) -> Self {
+    pub fn lambda(paramaters: Vec<Token<'source>>, bod: Vec<Stmt<'source>>, span: Span) -> Self {
         Self::Lambda {
             params: paramaters,
             body: bod,
+            span,
+        }
+    }
+
+    // The full extent of the expression in the source, from its first token to its last
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Assign { name, value } => name.span.to(value.span()),
+            Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+                left.span().to(right.span())
+            }
+            Expr::Call { callee, paren, .. } => callee.span().to(paren.span),
+            Expr::Unary { operator, right } => operator.span.to(right.span()),
+            Expr::Mutate {
+                operator, operand, ..
+            } => operator.span.to(operand.span()),
+            Expr::Variable { name } => name.span,
+            Expr::Ternary {
+                condition,
+                false_expr,
+                ..
+            } => condition.span().to(false_expr.span()),
+            Expr::Set { object, value, .. } => object.span().to(value.span()),
+            Expr::Super { keyword, method } => keyword.span.to(method.span),
+            Expr::This { keyword } => keyword.span,
+            Expr::Lambda { span, .. }
+            | Expr::Literal(_, span)
+            | Expr::Grouping(_, span)
+            | Expr::List { span, .. }
+            | Expr::Map { span, .. } => *span,
+            Expr::Get { object, name } => object.span().to(name.span),
+            Expr::Index {
+                object, bracket, ..
+            } => object.span().to(bracket.span),
+            Expr::IndexSet { object, value, .. } => object.span().to(value.span()),
         }
     }
 }
@@ -238,12 +294,30 @@ impl fmt::Display for Expr<'_> {
             } => {
                 write!(f, "({} {} {})", left, operator, right)
             }
-            Expr::Literal(lit) => write!(f, "{:#?}", lit),
+            Expr::Literal(lit, _) => write!(f, "{:#?}", lit),
             Expr::Get { object, name } => {
                 write!(f, "({}.{})", object, name)
             }
-            Expr::Grouping(expr) => write!(f, "(group {})", expr),
-            Expr::Lambda { params, body } => {
+            Expr::Grouping(expr, _) => write!(f, "(group {})", expr),
+            Expr::List { elements, .. } => {
+                let elements: Vec<String> = elements.iter().map(|e| e.to_string()).collect();
+                write!(f, "(list [{}])", elements.join(", "))
+            }
+            Expr::Map { entries, .. } => {
+                let entries: Vec<String> = entries
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key, value))
+                    .collect();
+                write!(f, "(map {{{}}})", entries.join(", "))
+            }
+            Expr::Index { object, index, .. } => write!(f, "({}[{}])", object, index),
+            Expr::IndexSet {
+                object,
+                index,
+                value,
+                ..
+            } => write!(f, "({}[{}] = {})", object, index, value),
+            Expr::Lambda { params, body, .. } => {
                 let param_names: Vec<&str> = params.iter().map(|p| p.lexeme).collect();
                 write!(f, "(lambda [{}] {:?})", param_names.join(", "), body)
             }
@@ -2,7 +2,7 @@
 // Defines the statement AST nodes for the rlox interpreter, including function, class, and control flow constructs.
 
 use crate::ast::expr::Expr;
-use crate::token::Token;
+use crate::token::{Span, Token};
 use std::rc::Rc;
 
 #[derive(Debug, Clone)]
@@ -10,15 +10,41 @@ pub struct FunctionDecl<'source> {
     pub name: Option<Token<'source>>,
     pub params: Vec<Token<'source>>,
     pub body: Vec<Stmt<'source>>,
+    pub span: Span,
+}
+
+// `static name = initializer;` in a class body
+#[derive(Debug, Clone)]
+pub struct FieldDecl<'source> {
+    pub name: Token<'source>,
+    pub initializer: Option<Rc<Expr<'source>>>,
+}
+
+// `catch (name) { body }`
+#[derive(Debug, Clone)]
+pub struct CatchClause<'source> {
+    pub name: Token<'source>,
+    pub body: Vec<Stmt<'source>>,
 }
 
 #[derive(Debug, Clone)]
 pub enum Stmt<'source> {
-    Block(Vec<Stmt<'source>>),
+    Block(Vec<Stmt<'source>>, Span),
     Class {
         name: Token<'source>,
         superclass: Option<Rc<Expr<'source>>>,
+        traits: Vec<Rc<Expr<'source>>>, // `with A, B`
         methods: Vec<FunctionDecl<'source>>,
+        static_methods: Vec<FunctionDecl<'source>>,
+        static_fields: Vec<FieldDecl<'source>>,
+        getters: Vec<FunctionDecl<'source>>, // `name { ... }`
+        setters: Vec<FunctionDecl<'source>>, // `set name(value) { ... }`
+        span: Span,
+    },
+    Trait {
+        name: Token<'source>,
+        methods: Vec<FunctionDecl<'source>>,
+        span: Span,
     },
     Expression(Rc<Expr<'source>>),
     Function(FunctionDecl<'source>),
@@ -26,8 +52,13 @@ pub enum Stmt<'source> {
         condition: Rc<Expr<'source>>,
         then_branch: Box<Stmt<'source>>,
         else_branch: Option<Box<Stmt<'source>>>,
+        span: Span,
+    },
+    Print {
+        keyword: Token<'source>,
+        value: Rc<Expr<'source>>,
+        span: Span,
     },
-    Print(Rc<Expr<'source>>),
     Return {
         keyword: Token<'source>,
         value: Option<Rc<Expr<'source>>>,
@@ -35,12 +66,70 @@ pub enum Stmt<'source> {
     Var {
         name: Token<'source>,
         initializer: Option<Rc<Expr<'source>>>,
+        span: Span,
     },
     While {
         condition: Rc<Expr<'source>>,
         body: Box<Stmt<'source>>,
+        increment: Option<Rc<Expr<'source>>>, // from a desugared `for`, runs even after `continue`
+        label: Option<Token<'source>>,        // `outer: while (...)`
+        span: Span,
+    },
+    // `for (name in iterable) body`, which runs the body with a fresh `name` for every element
+    ForIn {
+        name: Token<'source>,
+        iterable: Rc<Expr<'source>>,
+        body: Box<Stmt<'source>>,
+        label: Option<Token<'source>>,
+        span: Span,
+    },
+    // At least one of `catch` and `finally` is present
+    Try {
+        body: Vec<Stmt<'source>>,
+        catch: Option<CatchClause<'source>>,
+        finally: Option<Vec<Stmt<'source>>>,
+        span: Span,
+    },
+    Throw {
+        keyword: Token<'source>,
+        value: Rc<Expr<'source>>,
     },
     Break {
         keyword: Token<'source>,
+        label: Option<Token<'source>>,
+    },
+    Continue {
+        keyword: Token<'source>,
+        label: Option<Token<'source>>,
     },
 }
+
+impl Stmt<'_> {
+    // The full extent of the statement in the source. Statements whose extent is fully
+    // determined by their children (expression statements, `return`, `throw`, `break`, `continue`)
+    // don't store one.
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::Block(_, span)
+            | Stmt::Class { span, .. }
+            | Stmt::Trait { span, .. }
+            | Stmt::If { span, .. }
+            | Stmt::Print { span, .. }
+            | Stmt::Var { span, .. }
+            | Stmt::While { span, .. }
+            | Stmt::ForIn { span, .. }
+            | Stmt::Try { span, .. } => *span,
+            Stmt::Throw { keyword, value } => keyword.span.to(value.span()),
+            Stmt::Expression(expr) => expr.span(),
+            Stmt::Function(decl) => decl.span,
+            Stmt::Return { keyword, value } => match value {
+                Some(value) => keyword.span.to(value.span()),
+                None => keyword.span,
+            },
+            Stmt::Break { keyword, label } | Stmt::Continue { keyword, label } => match label {
+                Some(label) => keyword.span.to(label.span),
+                None => keyword.span,
+            },
+        }
+    }
+}
@@ -0,0 +1,124 @@
+// list.rs
+// Native functions for working with lists: len, push, pop, insert, remove, slice and range.
+
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    callable::{arg_error, list_arg, number_arg},
+    error::RuntimeError,
+    interpreter::{Interpreter, Range, Value},
+};
+
+// Turns a Lox number into a position inside a list of `length` elements.
+// The error message is shared by `xs[i]` and the natives below.
+pub fn list_index(index: f64, length: usize) -> Result<usize, String> {
+    checked_index(index, length, length)
+}
+
+// Like `list_index` but also accepts `length` itself, the position just past the last element
+fn boundary_index(index: f64, length: usize) -> Result<usize, String> {
+    checked_index(index, length, length + 1)
+}
+
+fn checked_index(index: f64, length: usize, limit: usize) -> Result<usize, String> {
+    if index.fract() != 0.0 {
+        return Err(format!("List index must be a whole number, got {}.", index));
+    }
+    if index < 0.0 || index >= limit as f64 {
+        return Err(format!(
+            "Index {} is out of bounds for a list of length {}.",
+            index, length
+        ));
+    }
+    Ok(index as usize)
+}
+
+// Number of elements in a list, entries in a map, or characters in a string
+pub fn len<'source>(
+    _interpreter: &mut Interpreter<'source>,
+    args: Vec<Value<'source>>,
+) -> Result<Value<'source>, RuntimeError<'source>> {
+    let length = match &args[0] {
+        Value::List(list) => list.borrow().len(),
+        Value::Map(map) => map.borrow().len(),
+        Value::String(s) => s.chars().count(),
+        other => return Err(arg_error(0, "list, map or string", Some(other))),
+    };
+    Ok(Value::Number(length as f64))
+}
+
+pub fn push<'source>(
+    _interpreter: &mut Interpreter<'source>,
+    mut args: Vec<Value<'source>>,
+) -> Result<Value<'source>, RuntimeError<'source>> {
+    let list = list_arg(&args, 0)?;
+    let value = args.pop().expect("arity is checked before the call");
+    list.borrow_mut().push(value);
+    Ok(Value::Nil)
+}
+
+pub fn pop<'source>(
+    _interpreter: &mut Interpreter<'source>,
+    args: Vec<Value<'source>>,
+) -> Result<Value<'source>, RuntimeError<'source>> {
+    let list = list_arg(&args, 0)?;
+    let value = list.borrow_mut().pop();
+    value.ok_or_else(|| "Can't pop from an empty list.".into())
+}
+
+// Inserts before `index`, so `insert(xs, len(xs), v)` appends
+pub fn insert<'source>(
+    _interpreter: &mut Interpreter<'source>,
+    mut args: Vec<Value<'source>>,
+) -> Result<Value<'source>, RuntimeError<'source>> {
+    let list = list_arg(&args, 0)?;
+    let index = number_arg(&args, 1)?;
+    let length = list.borrow().len();
+    let position = boundary_index(index, length)?;
+    let value = args.pop().expect("arity is checked before the call");
+    list.borrow_mut().insert(position, value);
+    Ok(Value::Nil)
+}
+
+// Removes the element at `index` and returns it
+pub fn remove<'source>(
+    _interpreter: &mut Interpreter<'source>,
+    args: Vec<Value<'source>>,
+) -> Result<Value<'source>, RuntimeError<'source>> {
+    let list = list_arg(&args, 0)?;
+    let index = number_arg(&args, 1)?;
+    let length = list.borrow().len();
+    let position = list_index(index, length)?;
+    let value = list.borrow_mut().remove(position);
+    Ok(value)
+}
+
+// A new list with the elements from `start` up to but not including `end`
+pub fn slice<'source>(
+    _interpreter: &mut Interpreter<'source>,
+    args: Vec<Value<'source>>,
+) -> Result<Value<'source>, RuntimeError<'source>> {
+    let list = list_arg(&args, 0)?;
+    let start = number_arg(&args, 1)?;
+    let end = number_arg(&args, 2)?;
+    let length = list.borrow().len();
+    let start = boundary_index(start, length)?;
+    let end = boundary_index(end, length)?;
+    if start > end {
+        return Err(format!("Slice start {} is after its end {}.", start, end).into());
+    }
+    let elements = list.borrow()[start..end].to_vec();
+    Ok(Value::List(Rc::new(RefCell::new(elements))))
+}
+
+// The numbers from `start` up to but not including `end`, `step` apart. Counts down when
+// `step` is negative.
+pub fn range<'source>(
+    _interpreter: &mut Interpreter<'source>,
+    args: Vec<Value<'source>>,
+) -> Result<Value<'source>, RuntimeError<'source>> {
+    let start = number_arg(&args, 0)?;
+    let end = number_arg(&args, 1)?;
+    let step = number_arg(&args, 2)?;
+    Ok(Value::Range(Range::new(start, end, step)?))
+}
@@ -3,9 +3,10 @@
 
 use crate::error::RuntimeError;
 use crate::interpreter::Interpreter;
-use crate::interpreter::Value;
+use crate::interpreter::{SharedList, SharedMap, Value};
 use std::fmt;
 use std::fmt::Debug;
+use std::rc::Rc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 pub trait Callable<'source>: Debug {
@@ -15,32 +16,147 @@ pub trait Callable<'source>: Debug {
         args: Vec<Value<'source>>,
     ) -> Result<Value<'source>, RuntimeError<'source>>;
     fn arity(&self) -> usize;
+    // Name shown for the call in tracebacks
+    fn name(&self) -> String;
 }
 
-#[derive(Debug)]
-pub struct Clock;
+// The Rust side of a native function. Arity is checked before it is called.
+pub type NativeFn<'source> = dyn Fn(
+        &mut Interpreter<'source>,
+        Vec<Value<'source>>,
+    ) -> Result<Value<'source>, RuntimeError<'source>>
+    + 'source;
 
-impl<'source> Callable<'source> for Clock {
+// A Rust closure exposed to Lox, see `Interpreter::define_native`
+#[derive(Clone)]
+pub struct NativeFunction<'source> {
+    name: String,
+    arity: usize,
+    function: Rc<NativeFn<'source>>,
+}
+
+impl<'source> NativeFunction<'source> {
+    pub fn new<F>(name: impl Into<String>, arity: usize, function: F) -> Self
+    where
+        F: Fn(
+                &mut Interpreter<'source>,
+                Vec<Value<'source>>,
+            ) -> Result<Value<'source>, RuntimeError<'source>>
+            + 'source,
+    {
+        Self {
+            name: name.into(),
+            arity,
+            function: Rc::new(function),
+        }
+    }
+}
+
+impl<'source> Callable<'source> for NativeFunction<'source> {
     fn call(
         &self,
-        _interpreter: &mut Interpreter<'source>,
-        _args: Vec<Value<'source>>,
+        interpreter: &mut Interpreter<'source>,
+        args: Vec<Value<'source>>,
     ) -> Result<Value<'source>, RuntimeError<'source>> {
-        let now = SystemTime::now();
-        let duration_since_epoch = now
-            .duration_since(UNIX_EPOCH)
-            .expect("SystemTime before UNIX_EPOCH!");
-        let seconds_since_epoch = Value::Number(duration_since_epoch.as_secs_f64());
-        Ok(seconds_since_epoch)
+        (self.function)(interpreter, args)
     }
 
     fn arity(&self) -> usize {
-        0
+        self.arity
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
     }
 }
 
-impl fmt::Display for Clock {
+impl Debug for NativeFunction<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "<native fn>")
+        write!(f, "<native fn {}>", self.name)
+    }
+}
+
+impl fmt::Display for NativeFunction<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn {}>", self.name)
+    }
+}
+
+// Argument helpers for native functions. They fail with a message naming the argument
+// (counting from 1) and the type that was passed instead.
+
+pub fn number_arg<'source>(
+    args: &[Value<'source>],
+    index: usize,
+) -> Result<f64, RuntimeError<'source>> {
+    match args.get(index) {
+        Some(Value::Number(n)) => Ok(*n),
+        other => Err(arg_error(index, "number", other)),
     }
 }
+
+pub fn string_arg<'a, 'source>(
+    args: &'a [Value<'source>],
+    index: usize,
+) -> Result<&'a str, RuntimeError<'source>> {
+    match args.get(index) {
+        Some(Value::String(s)) => Ok(s),
+        other => Err(arg_error(index, "string", other)),
+    }
+}
+
+pub fn bool_arg<'source>(
+    args: &[Value<'source>],
+    index: usize,
+) -> Result<bool, RuntimeError<'source>> {
+    match args.get(index) {
+        Some(Value::Bool(b)) => Ok(*b),
+        other => Err(arg_error(index, "boolean", other)),
+    }
+}
+
+pub fn list_arg<'source>(
+    args: &[Value<'source>],
+    index: usize,
+) -> Result<SharedList<'source>, RuntimeError<'source>> {
+    match args.get(index) {
+        Some(Value::List(list)) => Ok(list.clone()),
+        other => Err(arg_error(index, "list", other)),
+    }
+}
+
+pub fn map_arg<'source>(
+    args: &[Value<'source>],
+    index: usize,
+) -> Result<SharedMap<'source>, RuntimeError<'source>> {
+    match args.get(index) {
+        Some(Value::Map(map)) => Ok(map.clone()),
+        other => Err(arg_error(index, "map", other)),
+    }
+}
+
+pub(crate) fn arg_error<'source>(
+    index: usize,
+    expected: &str,
+    found: Option<&Value<'source>>,
+) -> RuntimeError<'source> {
+    let found = found.map_or("nothing", Value::type_name);
+    RuntimeError::NativeError(format!(
+        "Argument {} must be a {}, got {}.",
+        index + 1,
+        expected,
+        found
+    ))
+}
+
+// Seconds since the Unix epoch, for timing scripts
+pub fn clock<'source>(
+    _interpreter: &mut Interpreter<'source>,
+    _args: Vec<Value<'source>>,
+) -> Result<Value<'source>, RuntimeError<'source>> {
+    let now = SystemTime::now();
+    let duration_since_epoch = now
+        .duration_since(UNIX_EPOCH)
+        .expect("SystemTime before UNIX_EPOCH!");
+    Ok(Value::Number(duration_since_epoch.as_secs_f64()))
+}
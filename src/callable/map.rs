@@ -0,0 +1,51 @@
+// map.rs
+// Native functions for working with maps: keys, values, has and delete.
+
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    callable::map_arg,
+    error::RuntimeError,
+    interpreter::{Interpreter, MapKey, Value},
+};
+
+// The map's keys as a new list, in insertion order
+pub fn keys<'source>(
+    _interpreter: &mut Interpreter<'source>,
+    args: Vec<Value<'source>>,
+) -> Result<Value<'source>, RuntimeError<'source>> {
+    let map = map_arg(&args, 0)?;
+    let keys = map.borrow().keys().map(MapKey::to_value).collect();
+    Ok(Value::List(Rc::new(RefCell::new(keys))))
+}
+
+// The map's values as a new list, in the same order as `keys`
+pub fn values<'source>(
+    _interpreter: &mut Interpreter<'source>,
+    args: Vec<Value<'source>>,
+) -> Result<Value<'source>, RuntimeError<'source>> {
+    let map = map_arg(&args, 0)?;
+    let values = map.borrow().values().cloned().collect();
+    Ok(Value::List(Rc::new(RefCell::new(values))))
+}
+
+pub fn has<'source>(
+    _interpreter: &mut Interpreter<'source>,
+    args: Vec<Value<'source>>,
+) -> Result<Value<'source>, RuntimeError<'source>> {
+    let map = map_arg(&args, 0)?;
+    let key = MapKey::new(&args[1])?;
+    let found = map.borrow().contains_key(&key);
+    Ok(Value::Bool(found))
+}
+
+// Removes the key and returns whether it was there. The order of the other keys is kept.
+pub fn delete<'source>(
+    _interpreter: &mut Interpreter<'source>,
+    args: Vec<Value<'source>>,
+) -> Result<Value<'source>, RuntimeError<'source>> {
+    let map = map_arg(&args, 0)?;
+    let key = MapKey::new(&args[1])?;
+    let removed = map.borrow_mut().shift_remove(&key);
+    Ok(Value::Bool(removed.is_some()))
+}
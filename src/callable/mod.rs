@@ -1,5 +1,9 @@
 // mod.rs
 // Callable module entry point. Re-exports Callable trait and native functions.
 
+pub mod list;
 pub mod lox_callable;
+pub mod map;
+pub use list::*;
 pub use lox_callable::*;
+pub use map::*;
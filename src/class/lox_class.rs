@@ -13,26 +13,102 @@ use crate::{
     interpreter::{Interpreter, Value},
 };
 
+// The getters and setters of a class, by property name
+#[derive(Debug, Clone, Default)]
+pub struct Accessors<'source> {
+    pub getters: HashMap<String, Function<'source>>,
+    pub setters: HashMap<String, Function<'source>>,
+}
+
 #[derive(Debug, Clone)]
 pub struct LoxClass<'source> {
     pub name: String,
     pub superclass: Option<Rc<LoxClass<'source>>>,
     methods: HashMap<String, Function<'source>>,
+    static_methods: HashMap<String, Function<'source>>,
+    getters: HashMap<String, Function<'source>>,
+    setters: HashMap<String, Function<'source>>,
+    // Shared so that every instance's copy of the class sees the same values
+    static_fields: Rc<RefCell<HashMap<String, Value<'source>>>>,
 }
 
 impl<'source> LoxClass<'source> {
     pub fn new(
         name: String,
         methods: HashMap<String, Function<'source>>,
+        static_methods: HashMap<String, Function<'source>>,
+        accessors: Accessors<'source>,
         superclass: Option<Rc<LoxClass<'source>>>,
     ) -> Self {
         Self {
             name,
             methods,
+            static_methods,
+            getters: accessors.getters,
+            setters: accessors.setters,
+            static_fields: Rc::new(RefCell::new(HashMap::new())),
             superclass,
         }
     }
 
+    // `Class.name`: a static field, or else a static method bound to `class`. Both are
+    // inherited, a subclass sees its superclasses' statics unless it shadows them.
+    pub fn property(&self, class: Rc<LoxClass<'source>>, name: &str) -> Option<Value<'source>> {
+        if let Some(value) = self.find_static_field(name) {
+            return Some(value);
+        }
+        self.find_static_method(name)
+            .map(|method| Value::Callable(Rc::new(method.bind_class(class))))
+    }
+
+    // `Class.name = value` always sets the field on this class, never on a superclass
+    pub fn set_static(&self, name: &str, value: Value<'source>) {
+        self.static_fields
+            .borrow_mut()
+            .insert(name.to_string(), value);
+    }
+
+    fn find_static_field(&self, name: &str) -> Option<Value<'source>> {
+        if let Some(value) = self.static_fields.borrow().get(name) {
+            return Some(value.clone());
+        }
+        self.superclass
+            .as_ref()
+            .and_then(|superclass| superclass.find_static_field(name))
+    }
+
+    // Getters and methods are looked up one class at a time, nearest first, so a method in a
+    // subclass overrides an inherited getter of the same name (and the other way around)
+    pub fn find_getter(&self, name: &str) -> Option<&Function<'source>> {
+        if let Some(getter) = self.getters.get(name) {
+            return Some(getter);
+        }
+        if self.methods.contains_key(name) {
+            return None;
+        }
+        self.superclass
+            .as_ref()
+            .and_then(|superclass| superclass.find_getter(name))
+    }
+
+    pub fn find_setter(&self, name: &str) -> Option<&Function<'source>> {
+        if let Some(setter) = self.setters.get(name) {
+            return Some(setter);
+        }
+        self.superclass
+            .as_ref()
+            .and_then(|superclass| superclass.find_setter(name))
+    }
+
+    pub fn find_static_method(&self, name: &str) -> Option<&Function<'source>> {
+        if let Some(method) = self.static_methods.get(name) {
+            return Some(method);
+        }
+        self.superclass
+            .as_ref()
+            .and_then(|superclass| superclass.find_static_method(name))
+    }
+
     pub fn find_method(&self, name: &str) -> Option<&Function<'source>> {
         if let Some(method) = self.methods.get(name) {
             return Some(method);
@@ -66,6 +142,10 @@ impl<'source> Callable<'source> for LoxClass<'source> {
             0
         }
     }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
 }
 
 impl std::fmt::Display for LoxClass<'_> {
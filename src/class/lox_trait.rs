@@ -0,0 +1,70 @@
+// lox_trait.rs
+// Implements LoxTrait, a named set of methods that classes mix in with `with`.
+
+use std::rc::Rc;
+
+use crate::{
+    ast::stmt::FunctionDecl,
+    class::LoxClass,
+    environment::{Environment, SharedEnv},
+    function::Function,
+    interpreter::Value,
+};
+
+#[derive(Clone)]
+pub struct LoxTrait<'source> {
+    pub name: String,
+    methods: Vec<FunctionDecl<'source>>,
+    closure: SharedEnv<'source>, // where the trait was declared
+}
+
+impl<'source> LoxTrait<'source> {
+    pub fn new(
+        name: String,
+        methods: Vec<FunctionDecl<'source>>,
+        closure: SharedEnv<'source>,
+    ) -> Self {
+        Self {
+            name,
+            methods,
+            closure,
+        }
+    }
+
+    // The trait's methods as they behave inside one particular class. `super` in a trait
+    // method means the superclass of the class it is mixed into, so it is bound here rather
+    // than when the trait is declared.
+    pub fn methods_for(
+        &self,
+        superclass: Option<&Rc<LoxClass<'source>>>,
+    ) -> Vec<(String, Function<'source>)> {
+        let env = Environment::from_enclosing(self.closure.clone());
+        let super_value = superclass.map_or(Value::Nil, |class| Value::Class(class.clone()));
+        env.borrow_mut().define("super".to_string(), super_value);
+
+        self.methods
+            .iter()
+            .filter_map(|method| {
+                let name = method.name.as_ref()?.lexeme.to_string();
+                let function = if name == "init" {
+                    Function::new_initializer(method.clone(), env.clone())
+                } else {
+                    Function::new(method.clone(), env.clone())
+                };
+                Some((name, function))
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Debug for LoxTrait<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<trait {}>", self.name)
+    }
+}
+
+impl std::fmt::Display for LoxTrait<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
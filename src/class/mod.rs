@@ -1,5 +1,7 @@
 // mod.rs
-// Class module entry point. Re-exports LoxClass functionality.
+// Class module entry point. Re-exports LoxClass and LoxTrait functionality.
 
 pub mod lox_class;
+pub mod lox_trait;
 pub use lox_class::*;
+pub use lox_trait::*;
@@ -0,0 +1,5 @@
+// mod.rs
+// Diagnostic module entry point. Re-exports the diagnostic renderer.
+
+pub mod render;
+pub use render::*;
@@ -0,0 +1,347 @@
+// render.rs
+// Renders errors from every phase as rustc-style diagnostics with a source snippet and carets.
+
+use std::fmt::Write;
+
+use crate::{
+    error::{CompilerError, LoxError, ParserError, RuntimeError, ScannerError},
+    interpreter::{CallFrame, HOST_LINE},
+    token::{Span, Token, TokenType},
+};
+
+// A single report: what went wrong, where, and optionally how to fix it.
+// Errors are turned into diagnostics through the `From` impls at the bottom of this file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub kind: &'static str,
+    pub message: String,
+    pub span: Option<Span>,
+    pub notes: Vec<String>,
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(kind: &'static str, message: impl Into<String>, span: Option<Span>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            span,
+            notes: Vec::new(),
+            help: None,
+        }
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    // Adds one note per active call, innermost first, e.g. `= note: in fib(), called at line 7`.
+    // Very deep stacks only show their ends, the middle of a runaway recursion says nothing new.
+    pub fn with_traceback(mut self, frames: &[CallFrame]) -> Self {
+        const SHOWN: usize = 10; // frames kept at each end of a long traceback
+
+        let note = |frame: &CallFrame| match frame.line {
+            HOST_LINE => format!("in {}(), called from the host", frame.function),
+            line => format!("in {}(), called at line {}", frame.function, line),
+        };
+        if frames.len() > 2 * SHOWN {
+            self.notes.extend(frames[..SHOWN].iter().map(note));
+            self.notes
+                .push(format!("... {} more calls ...", frames.len() - 2 * SHOWN));
+            self.notes
+                .extend(frames[frames.len() - SHOWN..].iter().map(note));
+        } else {
+            self.notes.extend(frames.iter().map(note));
+        }
+        self
+    }
+
+    // Renders the diagnostic against the source it was produced from:
+    //
+    // Runtime error: Operands of '-' must be numbers.
+    //  --> script.lox:3:7
+    //   |
+    // 3 | print "a" - 1;
+    //   |       ^^^^^^^
+    //   = help: ...
+    pub fn render(&self, file_name: &str, source: &str) -> String {
+        let mut out = String::new();
+        writeln!(out, "{}: {}", self.kind, self.message).unwrap();
+
+        let gutter = match self.span {
+            Some(span) => {
+                let location = Location::find(source, span);
+                let width = location.line.to_string().len();
+                let pad = " ".repeat(width);
+                writeln!(
+                    out,
+                    "{}--> {}:{}:{}",
+                    pad, file_name, location.line, location.column
+                )
+                .unwrap();
+                writeln!(out, "{} |", pad).unwrap();
+                writeln!(out, "{} | {}", location.line, location.text).unwrap();
+                writeln!(
+                    out,
+                    "{} | {}{}",
+                    pad,
+                    " ".repeat(location.column - 1),
+                    "^".repeat(location.width)
+                )
+                .unwrap();
+                pad
+            }
+            None => {
+                writeln!(out, "--> {}", file_name).unwrap();
+                String::new()
+            }
+        };
+
+        for note in &self.notes {
+            writeln!(out, "{} = note: {}", gutter, note).unwrap();
+        }
+        if let Some(help) = &self.help {
+            writeln!(out, "{} = help: {}", gutter, help).unwrap();
+        }
+        out
+    }
+}
+
+// Where a span starts, as a human would count it, plus the line it starts on
+struct Location<'a> {
+    line: usize,
+    column: usize,
+    text: &'a str,
+    width: usize, // number of carets, at least one so empty spans (EOF) stay visible
+}
+
+impl<'a> Location<'a> {
+    fn find(source: &'a str, span: Span) -> Self {
+        let start = floor_char_boundary(source, span.start);
+        let end = floor_char_boundary(source, span.end.max(start));
+
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |i| start + i);
+        let text = source[line_start..line_end].trim_end_matches('\r');
+
+        // Spans covering several lines are underlined up to the end of their first line
+        let underline_end = end.min(line_start + text.len());
+        Self {
+            line: source[..start].matches('\n').count() + 1,
+            column: source[line_start..start].chars().count() + 1,
+            text,
+            width: source[start..underline_end.max(start)]
+                .chars()
+                .count()
+                .max(1),
+        }
+    }
+}
+
+fn floor_char_boundary(source: &str, mut index: usize) -> usize {
+    index = index.min(source.len());
+    while !source.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+fn describe(token: &Token) -> String {
+    match token.kind {
+        TokenType::Eof => "end of input".to_string(),
+        _ => format!("'{}'", token.lexeme),
+    }
+}
+
+impl From<&ScannerError> for Diagnostic {
+    fn from(error: &ScannerError) -> Self {
+        let kind = "Scanner error";
+        let span = error.span();
+        match error {
+            ScannerError::Io(e) => Diagnostic::new(kind, format!("io error: {}", e), span),
+            ScannerError::UnexpectedChar(c, ..) => {
+                Diagnostic::new(kind, format!("Unexpected character '{}'.", c), span)
+            }
+            ScannerError::UnterminatedString(..) => {
+                Diagnostic::new(kind, "Unterminated string.", span)
+                    .with_help("add a closing '\"' to end the string")
+            }
+            ScannerError::UnterminatedEscape(..) => {
+                Diagnostic::new(kind, "Unterminated escape sequence.", span)
+            }
+            ScannerError::UnterminatedComment(..) => {
+                Diagnostic::new(kind, "Unterminated comment.", span)
+                    .with_help("block comments are closed with '*/'")
+            }
+        }
+    }
+}
+
+impl From<&ParserError<'_>> for Diagnostic {
+    fn from(error: &ParserError<'_>) -> Self {
+        let kind = "Parser error";
+        let span = error.span();
+        match error {
+            ParserError::Io(e) => Diagnostic::new(kind, format!("io error: {}", e), span),
+            ParserError::UnterminatedParen { .. } => {
+                Diagnostic::new(kind, "Unterminated grouping.", span)
+                    .with_help("add a ')' to close this '('")
+            }
+            ParserError::UnexpectedExpression { found, .. } => Diagnostic::new(
+                kind,
+                format!("Expected expression, found {}.", describe(found)),
+                span,
+            ),
+            ParserError::UnexpectedToken {
+                expected, found, ..
+            } => Diagnostic::new(
+                kind,
+                format!("Expected '{}', found {}.", expected, describe(found)),
+                span,
+            ),
+            ParserError::UnexpectedEof { expected, .. } => {
+                Diagnostic::new(kind, "Unexpected end of input.", span).with_note(expected.clone())
+            }
+            ParserError::InvalidAssignmentTarget { .. } => {
+                Diagnostic::new(kind, "Invalid assignment target.", span)
+            }
+            ParserError::BreakException { .. } => {
+                Diagnostic::new(kind, "Can't use 'break' outside of a loop.", span)
+            }
+            ParserError::ContinueException { .. } => {
+                Diagnostic::new(kind, "Can't use 'continue' outside of a loop.", span)
+            }
+            ParserError::TooManyParams { .. } => {
+                Diagnostic::new(kind, "Can't have more than 255 parameters.", span)
+            }
+            ParserError::TooManyArgs { .. } => {
+                Diagnostic::new(kind, "Can't have more than 255 arguments.", span)
+            }
+            ParserError::LabelWithoutLoop { .. } => {
+                Diagnostic::new(kind, "Only loops can be labeled.", span)
+            }
+            ParserError::SetterArity { .. } => {
+                Diagnostic::new(kind, "A setter must take exactly one parameter.", span)
+            }
+        }
+    }
+}
+
+impl From<&CompilerError<'_>> for Diagnostic {
+    fn from(error: &CompilerError<'_>) -> Self {
+        let kind = "Resolver error";
+        let span = error.span();
+        match error {
+            CompilerError::LocalVarDecl { .. } => Diagnostic::new(
+                kind,
+                "Can't read local variable in its own initializer.",
+                span,
+            ),
+            CompilerError::ExistingVar { name } => Diagnostic::new(
+                kind,
+                format!("Already a variable named '{}' in this scope.", name.lexeme),
+                span,
+            ),
+            CompilerError::IllegalReturn { .. } => {
+                Diagnostic::new(kind, "Can't return from top-level code.", span)
+            }
+            CompilerError::ThisOutsideClass { .. } => {
+                Diagnostic::new(kind, "Can't use 'this' outside of a class.", span)
+            }
+            CompilerError::InitializerReturn { .. } => {
+                Diagnostic::new(kind, "Can't return a value from an initializer.", span)
+                    .with_note("initializers always return the new instance")
+            }
+            CompilerError::SelfInheritance { .. } => {
+                Diagnostic::new(kind, "A class can't inherit from itself.", span)
+            }
+            CompilerError::SuperTypeError { msg, .. } => Diagnostic::new(kind, msg.clone(), span),
+            CompilerError::UndefinedLabel { label } => Diagnostic::new(
+                kind,
+                format!("No enclosing loop is labeled '{}'.", label.lexeme),
+                span,
+            ),
+        }
+    }
+}
+
+impl From<&RuntimeError<'_>> for Diagnostic {
+    fn from(error: &RuntimeError<'_>) -> Self {
+        let kind = "Runtime error";
+        let span = error.span();
+        match error {
+            RuntimeError::UnaryMinus { .. } => {
+                Diagnostic::new(kind, "Operand of unary '-' must be a number.", span)
+            }
+            RuntimeError::BinaryPlus { .. } => {
+                Diagnostic::new(kind, "Operands of '+' must be numbers or strings.", span)
+            }
+            RuntimeError::BinaryMinus { lexeme, .. }
+            | RuntimeError::BinaryMult { lexeme, .. }
+            | RuntimeError::BinaryDiv { lexeme, .. }
+            | RuntimeError::BinaryComp { lexeme, .. } => Diagnostic::new(
+                kind,
+                format!("Operands of '{}' must be numbers.", lexeme),
+                span,
+            ),
+            RuntimeError::BinaryDBZ { .. } => Diagnostic::new(kind, "Division by zero.", span),
+            RuntimeError::UndefinedVariable { found, .. } => {
+                Diagnostic::new(kind, format!("Undefined variable '{}'.", found), span)
+            }
+            RuntimeError::MutationError { .. } => Diagnostic::new(
+                kind,
+                "Only variables holding numbers can be incremented or decremented.",
+                span,
+            ),
+            RuntimeError::FunctionError { message, .. } => {
+                Diagnostic::new(kind, message.clone(), span)
+            }
+            RuntimeError::TypeError { msg, .. } | RuntimeError::IndexError { msg, .. } => {
+                Diagnostic::new(kind, msg.clone(), span)
+            }
+            RuntimeError::StackOverflow { .. } => Diagnostic::new(kind, "Stack overflow.", span)
+                .with_help("check for recursion without a base case"),
+            RuntimeError::NativeError(message) => Diagnostic::new(kind, message.clone(), span),
+            RuntimeError::Thrown { value, .. } => {
+                Diagnostic::new(kind, uncaught_exception(&value.to_string()), span)
+            }
+            RuntimeError::Io(_)
+            | RuntimeError::BreakException(_)
+            | RuntimeError::ContinueException(_)
+            | RuntimeError::ReturnException(_) => Diagnostic::new(kind, error.to_string(), span),
+        }
+    }
+}
+
+impl LoxError<'_> {
+    // One diagnostic per error, in the order the phases found them
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        match self {
+            LoxError::Syntax { scanner, parser } => scanner
+                .iter()
+                .map(Diagnostic::from)
+                .chain(parser.iter().map(Diagnostic::from))
+                .collect(),
+            LoxError::Resolve(errors) => errors.iter().map(Diagnostic::from).collect(),
+            LoxError::Runtime { error, traceback } => {
+                vec![Diagnostic::from(error).with_traceback(traceback)]
+            }
+            LoxError::Call(message) => {
+                vec![Diagnostic::new("Runtime error", message.clone(), None)]
+            }
+        }
+    }
+}
+
+// The message for an exception nothing caught, given the thrown value as text
+pub fn uncaught_exception(text: &str) -> String {
+    format!("Uncaught exception: {}", text)
+}
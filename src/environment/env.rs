@@ -31,6 +31,11 @@ impl<'source> Environment<'source> {
         self.values.insert(name, val);
     }
 
+    // Names and values defined directly in this scope (enclosing scopes are not included)
+    pub fn bindings(&self) -> impl Iterator<Item = (&String, &Value<'source>)> {
+        self.values.iter()
+    }
+
     pub fn ancestor(env: SharedEnv<'source>, distance: usize) -> Option<SharedEnv<'source>> {
         let mut current = env;
 
@@ -58,6 +63,8 @@ impl<'source> Environment<'source> {
         } else {
             Err(RuntimeError::UndefinedVariable {
                 found: name.lexeme.to_string(),
+                line: Some(name.line),
+                span: Some(name.span),
             })
         }
     }
@@ -73,11 +80,15 @@ impl<'source> Environment<'source> {
             } else {
                 Err(RuntimeError::UndefinedVariable {
                     found: name.to_string(),
+                    line: None,
+                    span: None,
                 })
             }
         } else {
             Err(RuntimeError::UndefinedVariable {
                 found: name.to_string(),
+                line: None,
+                span: None,
             })
         }
     }
@@ -93,6 +104,8 @@ impl<'source> Environment<'source> {
         } else {
             Err(RuntimeError::UndefinedVariable {
                 found: name.lexeme.to_string(),
+                line: Some(name.line),
+                span: Some(name.span),
             })
         }
     }
@@ -105,6 +118,8 @@ impl<'source> Environment<'source> {
         } else {
             Err(RuntimeError::UndefinedVariable {
                 found: name.lexeme.to_string(),
+                line: Some(name.line),
+                span: Some(name.span),
             })
         }
     }
@@ -123,7 +138,11 @@ impl<'source> Environment<'source> {
             if let Some(enclosing) = self.enclosing.clone() {
                 enclosing.borrow_mut().assign(name, val)
             } else {
-                Err(RuntimeError::UndefinedVariable { found: key })
+                Err(RuntimeError::UndefinedVariable {
+                    found: key,
+                    line: Some(name.line),
+                    span: Some(name.span),
+                })
             }
         }
     }
@@ -1,8 +1,8 @@
 // err.rs
 // Defines error types for scanner, parser, runtime, and compiler phases in rlox.
 
-use crate::interpreter::Value;
-use crate::token::{Token, TokenType};
+use crate::interpreter::{CallFrame, Value};
+use crate::token::{Span, Token, TokenType};
 use std::{fmt, io};
 
 // In the Java implementation, error handling was more rudimentary,
@@ -10,10 +10,10 @@ use std::{fmt, io};
 #[derive(Debug)]
 pub enum ScannerError {
     Io(io::Error),
-    UnexpectedChar(char, usize),
-    UnterminatedString(usize),
-    UnterminatedEscape(usize),
-    UnterminatedComment(usize),
+    UnexpectedChar(char, usize, Span),
+    UnterminatedString(usize, Span),
+    UnterminatedEscape(usize, Span),
+    UnterminatedComment(usize, Span),
 }
 
 #[derive(Debug)]
@@ -21,6 +21,7 @@ pub enum ParserError<'source> {
     Io(io::Error),
     UnterminatedParen {
         line: usize,
+        span: Span,
     },
     UnexpectedExpression {
         found: Token<'source>,
@@ -34,6 +35,7 @@ pub enum ParserError<'source> {
     UnexpectedEof {
         expected: String,
         line: usize,
+        span: Span,
     },
     InvalidAssignmentTarget {
         found: Token<'source>,
@@ -41,69 +43,248 @@ pub enum ParserError<'source> {
     },
     BreakException {
         line: usize,
+        span: Span,
+    },
+    ContinueException {
+        line: usize,
+        span: Span,
     },
     TooManyParams {
         line: usize,
+        span: Span,
+    },
+    TooManyArgs {
+        line: usize,
+        span: Span,
+    },
+    LabelWithoutLoop {
+        line: usize,
+        span: Span,
+    },
+    SetterArity {
+        line: usize,
+        span: Span,
     },
 }
 
+#[derive(Debug)]
 pub enum RuntimeError<'source> {
     Io(io::Error),
     UnaryMinus {
         lexeme: String,
         line: usize,
+        span: Span,
     },
     BinaryMinus {
         lexeme: String,
         line: usize,
+        span: Span,
     },
     BinaryPlus {
         lexeme: String,
         line: usize,
+        span: Span,
     },
     BinaryMult {
         lexeme: String,
         line: usize,
+        span: Span,
     },
     BinaryDiv {
         lexeme: String,
         line: usize,
+        span: Span,
     },
     BinaryComp {
         lexeme: String,
         line: usize,
+        span: Span,
     },
     BinaryDBZ {
         line: usize,
+        span: Span,
     },
     UndefinedVariable {
         found: String,
+        line: Option<usize>,
+        span: Option<Span>, // None for names the interpreter looks up itself, like `this`
     },
-    BreakException,
+    BreakException(Option<&'source str>), // the label of the loop to leave, if any
+    ContinueException(Option<&'source str>),
     MutationError {
         lexeme: String,
         line: usize,
+        span: Span,
     },
     FunctionError {
         lexeme: String,
         line: usize,
         message: String,
+        span: Span,
     },
     ReturnException(Value<'source>),
     TypeError {
         msg: String,
         line: usize,
+        span: Span,
+    },
+    StackOverflow {
+        line: usize,
+        span: Span,
+    },
+    IndexError {
+        msg: String,
+        line: usize,
+        span: Span,
     },
+    // A value raised by `throw` that no `catch` handled
+    Thrown {
+        value: Value<'source>,
+        line: usize,
+        span: Span,
+    },
+    // Raised by native functions, which don't know where they were called from.
+    // The interpreter turns it into a FunctionError at the call site.
+    NativeError(String),
 }
 
+#[derive(Debug)]
 pub enum CompilerError<'source> {
-    LocalVarDecl { name: Token<'source> },
-    ExistingVar { line: usize },
-    IllegalReturn { keyword: Token<'source> },
-    ThisOutsideClass { keyword: Token<'source> },
-    InitializerReturn { keyword: Token<'source> },
-    SelfInheritance { line: usize },
-    SuperTypeError { msg: String, line: usize },
+    LocalVarDecl {
+        name: Token<'source>,
+    },
+    ExistingVar {
+        name: Token<'source>,
+    },
+    IllegalReturn {
+        keyword: Token<'source>,
+    },
+    ThisOutsideClass {
+        keyword: Token<'source>,
+    },
+    InitializerReturn {
+        keyword: Token<'source>,
+    },
+    SelfInheritance {
+        name: Token<'source>,
+    },
+    SuperTypeError {
+        msg: String,
+        keyword: Token<'source>,
+    },
+    UndefinedLabel {
+        label: Token<'source>,
+    },
+}
+
+impl ScannerError {
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            ScannerError::Io(_) => None,
+            ScannerError::UnexpectedChar(_, _, span)
+            | ScannerError::UnterminatedString(_, span)
+            | ScannerError::UnterminatedEscape(_, span)
+            | ScannerError::UnterminatedComment(_, span) => Some(*span),
+        }
+    }
+}
+
+impl ParserError<'_> {
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            ParserError::Io(_) => None,
+            ParserError::UnexpectedExpression { found, .. }
+            | ParserError::UnexpectedToken { found, .. }
+            | ParserError::InvalidAssignmentTarget { found, .. } => Some(found.span),
+            ParserError::UnterminatedParen { span, .. }
+            | ParserError::UnexpectedEof { span, .. }
+            | ParserError::BreakException { span, .. }
+            | ParserError::ContinueException { span, .. }
+            | ParserError::TooManyParams { span, .. }
+            | ParserError::TooManyArgs { span, .. }
+            | ParserError::LabelWithoutLoop { span, .. }
+            | ParserError::SetterArity { span, .. } => Some(*span),
+        }
+    }
+}
+
+impl RuntimeError<'_> {
+    // Control flow signals (break, continue, return), io failures and native errors have no location
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            RuntimeError::Io(_)
+            | RuntimeError::BreakException(_)
+            | RuntimeError::ContinueException(_)
+            | RuntimeError::ReturnException(_)
+            | RuntimeError::NativeError(_) => None,
+            RuntimeError::UndefinedVariable { span, .. } => *span,
+            RuntimeError::UnaryMinus { span, .. }
+            | RuntimeError::BinaryMinus { span, .. }
+            | RuntimeError::BinaryPlus { span, .. }
+            | RuntimeError::BinaryMult { span, .. }
+            | RuntimeError::BinaryDiv { span, .. }
+            | RuntimeError::BinaryComp { span, .. }
+            | RuntimeError::BinaryDBZ { span, .. }
+            | RuntimeError::MutationError { span, .. }
+            | RuntimeError::FunctionError { span, .. }
+            | RuntimeError::TypeError { span, .. }
+            | RuntimeError::StackOverflow { span, .. }
+            | RuntimeError::IndexError { span, .. }
+            | RuntimeError::Thrown { span, .. } => Some(*span),
+        }
+    }
+
+    // The line the error was raised on, for the error values `catch` hands to scripts
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            RuntimeError::Io(_)
+            | RuntimeError::BreakException(_)
+            | RuntimeError::ContinueException(_)
+            | RuntimeError::ReturnException(_)
+            | RuntimeError::NativeError(_) => None,
+            RuntimeError::UndefinedVariable { line, .. } => *line,
+            RuntimeError::UnaryMinus { line, .. }
+            | RuntimeError::BinaryMinus { line, .. }
+            | RuntimeError::BinaryPlus { line, .. }
+            | RuntimeError::BinaryMult { line, .. }
+            | RuntimeError::BinaryDiv { line, .. }
+            | RuntimeError::BinaryComp { line, .. }
+            | RuntimeError::BinaryDBZ { line, .. }
+            | RuntimeError::MutationError { line, .. }
+            | RuntimeError::FunctionError { line, .. }
+            | RuntimeError::TypeError { line, .. }
+            | RuntimeError::StackOverflow { line, .. }
+            | RuntimeError::IndexError { line, .. }
+            | RuntimeError::Thrown { line, .. } => Some(*line),
+        }
+    }
+
+    // Whether `catch` can handle the error. Control flow signals only look like errors, and
+    // a failing output sink is a problem with the host rather than the script.
+    pub fn is_catchable(&self) -> bool {
+        !matches!(
+            self,
+            RuntimeError::Io(_)
+                | RuntimeError::BreakException(_)
+                | RuntimeError::ContinueException(_)
+                | RuntimeError::ReturnException(_)
+        )
+    }
+}
+
+impl CompilerError<'_> {
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            CompilerError::LocalVarDecl { name }
+            | CompilerError::ExistingVar { name }
+            | CompilerError::SelfInheritance { name } => Some(name.span),
+            CompilerError::IllegalReturn { keyword }
+            | CompilerError::ThisOutsideClass { keyword }
+            | CompilerError::InitializerReturn { keyword }
+            | CompilerError::SuperTypeError { keyword, .. } => Some(keyword.span),
+            CompilerError::UndefinedLabel { label } => Some(label.span),
+        }
+    }
 }
 
 impl fmt::Display for CompilerError<'_> {
@@ -116,11 +297,11 @@ impl fmt::Display for CompilerError<'_> {
                     name
                 )
             }
-            CompilerError::ExistingVar { line } => {
+            CompilerError::ExistingVar { name } => {
                 write!(
                     f,
                     "Already a variable with this name in this scope. | Found on line {}",
-                    line
+                    name.line
                 )
             }
             CompilerError::IllegalReturn { keyword } => {
@@ -136,15 +317,22 @@ impl fmt::Display for CompilerError<'_> {
             CompilerError::InitializerReturn { keyword } => {
                 write!(f, "Can't return a value from an initializer. - {}", keyword)
             }
-            CompilerError::SelfInheritance { line } => {
+            CompilerError::SelfInheritance { name } => {
                 write!(
                     f,
                     "A class can't inherit from itself. Error on line {}",
-                    line
+                    name.line
                 )
             }
-            CompilerError::SuperTypeError { msg, line } => {
-                write!(f, "{} on line {}", msg, line)
+            CompilerError::SuperTypeError { msg, keyword } => {
+                write!(f, "{} on line {}", msg, keyword.line)
+            }
+            CompilerError::UndefinedLabel { label } => {
+                write!(
+                    f,
+                    "No enclosing loop labeled '{}' on line {}",
+                    label.lexeme, label.line
+                )
             }
         }
     }
@@ -154,58 +342,61 @@ impl fmt::Display for RuntimeError<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             RuntimeError::Io(e) => write!(f, "io error: {}", e),
-            RuntimeError::UnaryMinus { lexeme, line } => {
+            RuntimeError::UnaryMinus { lexeme, line, .. } => {
                 write!(
                     f,
                     "Unary minus applied applied to non-number | violator: '{}' on line {}",
                     lexeme, line
                 )
             }
-            RuntimeError::BinaryPlus { lexeme, line } => {
+            RuntimeError::BinaryPlus { lexeme, line, .. } => {
                 write!(
                     f,
                     "Addition attempted on non-number/non-string values | violator: '{}' on line {}",
                     lexeme, line
                 )
             }
-            RuntimeError::BinaryMinus { lexeme, line } => {
+            RuntimeError::BinaryMinus { lexeme, line, .. } => {
                 write!(
                     f,
                     "Subtraction attempted on non-number values | violator: '{}' on line {}",
                     lexeme, line
                 )
             }
-            RuntimeError::BinaryMult { lexeme, line } => {
+            RuntimeError::BinaryMult { lexeme, line, .. } => {
                 write!(
                     f,
                     "Multiplication attempted on non-number values | violator: '{}' on line {}",
                     lexeme, line
                 )
             }
-            RuntimeError::BinaryDiv { lexeme, line } => {
+            RuntimeError::BinaryDiv { lexeme, line, .. } => {
                 write!(
                     f,
                     "Division attempted on non-number values | violator: '{}' on line {}",
                     lexeme, line
                 )
             }
-            RuntimeError::BinaryComp { lexeme, line } => {
+            RuntimeError::BinaryComp { lexeme, line, .. } => {
                 write!(
                     f,
                     "Comparison check attempted on non-number/non-string values | violator: '{}' on line {}",
                     lexeme, line
                 )
             }
-            RuntimeError::BinaryDBZ { line } => {
+            RuntimeError::BinaryDBZ { line, .. } => {
                 write!(f, "Division by zero  on line {}", line)
             }
-            RuntimeError::UndefinedVariable { found } => {
+            RuntimeError::UndefinedVariable { found, .. } => {
                 write!(f, "Undefined variable '{}'. ", found)
             }
-            RuntimeError::BreakException => {
+            RuntimeError::BreakException(_) => {
                 write!(f, "Break statement execute.")
             }
-            RuntimeError::MutationError { lexeme, line } => {
+            RuntimeError::ContinueException(_) => {
+                write!(f, "Continue statement execute.")
+            }
+            RuntimeError::MutationError { lexeme, line, .. } => {
                 write!(
                     f,
                     "Mutation attempted on illegal expression |'{}' line: {}",
@@ -216,23 +407,45 @@ impl fmt::Display for RuntimeError<'_> {
                 lexeme,
                 line,
                 message,
+                ..
             } => {
                 write!(f, "Here {} on line {} - {}", lexeme, line, message)
             }
             RuntimeError::ReturnException(val) => write!(f, "{}", val),
-            RuntimeError::TypeError { msg, line } => {
+            RuntimeError::TypeError { msg, line, .. }
+            | RuntimeError::IndexError { msg, line, .. } => {
                 write!(f, "{} on line {}", msg, line)
             }
+            RuntimeError::StackOverflow { line, .. } => {
+                write!(f, "Stack overflow on line {}", line)
+            }
+            RuntimeError::NativeError(message) => write!(f, "{}", message),
+            RuntimeError::Thrown { value, line, .. } => {
+                write!(f, "Uncaught exception: {} on line {}", value, line)
+            }
         }
     }
 }
 
+// Lets native functions report failures with `?` or `.into()`
+impl From<String> for RuntimeError<'_> {
+    fn from(message: String) -> Self {
+        RuntimeError::NativeError(message)
+    }
+}
+
+impl From<&str> for RuntimeError<'_> {
+    fn from(message: &str) -> Self {
+        RuntimeError::NativeError(message.to_string())
+    }
+}
+
 // Display implementation for ParserError
 impl fmt::Display for ParserError<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ParserError::Io(e) => write!(f, "io error: {}", e),
-            ParserError::UnterminatedParen { line } => {
+            ParserError::UnterminatedParen { line, .. } => {
                 write!(f, "You have an unterminated grouping on line {}", line)
             }
             ParserError::UnexpectedToken {
@@ -246,7 +459,7 @@ impl fmt::Display for ParserError<'_> {
                     expected, found, line
                 )
             }
-            ParserError::UnexpectedEof { expected, line } => {
+            ParserError::UnexpectedEof { expected, line, .. } => {
                 write!(f, "Unexpected end of file: '{}' on line {}", expected, line)
             }
             ParserError::UnexpectedExpression { found, line } => {
@@ -255,39 +468,91 @@ impl fmt::Display for ParserError<'_> {
             ParserError::InvalidAssignmentTarget { found, line } => {
                 write!(f, "Invalid assignment target '{}' on line {}", found, line)
             }
-            ParserError::BreakException { line } => {
+            ParserError::BreakException { line, .. } => {
                 write!(
                     f,
                     "Cannot use break outside of a loop | Issue found on line {}.",
                     line
                 )
             }
-            ParserError::TooManyParams { line } => {
+            ParserError::ContinueException { line, .. } => {
+                write!(
+                    f,
+                    "Cannot use continue outside of a loop | Issue found on line {}.",
+                    line
+                )
+            }
+            ParserError::TooManyParams { line, .. } => {
                 write!(
                     f,
                     "Parameters for a function cannot exceed 255. | line {}.",
                     line
                 )
             }
+            ParserError::TooManyArgs { line, .. } => {
+                write!(f, "Arguments to a call cannot exceed 255. | line {}.", line)
+            }
+            ParserError::LabelWithoutLoop { line, .. } => {
+                write!(f, "Only loops can be labeled | line {}.", line)
+            }
+            ParserError::SetterArity { line, .. } => {
+                write!(f, "Setters take exactly one parameter | line {}.", line)
+            }
         }
     }
 }
 
+// Everything that can go wrong when running source through `Lox`
+#[derive(Debug)]
+pub enum LoxError<'source> {
+    Syntax {
+        scanner: Vec<ScannerError>,
+        parser: Vec<ParserError<'source>>,
+    },
+    Resolve(Vec<CompilerError<'source>>),
+    Runtime {
+        error: RuntimeError<'source>,
+        traceback: Vec<CallFrame>, // innermost call first
+    },
+    // The host called something that isn't a function, passed the wrong number of arguments,
+    // or the native it called failed
+    Call(String),
+}
+
+impl LoxError<'_> {
+    // Whether the source was rejected before it started running
+    pub fn is_compile_error(&self) -> bool {
+        matches!(self, LoxError::Syntax { .. } | LoxError::Resolve(_))
+    }
+}
+
+// One `Kind: message` line per error, without source snippets; use `diagnostics` for those
+impl fmt::Display for LoxError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines: Vec<String> = self
+            .diagnostics()
+            .iter()
+            .map(|d| format!("{}: {}", d.kind, d.message))
+            .collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
 // Display implementation for ScannerError
 impl fmt::Display for ScannerError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ScannerError::Io(e) => write!(f, "io error: {}", e),
-            ScannerError::UnexpectedChar(c, line) => {
+            ScannerError::UnexpectedChar(c, line, _) => {
                 write!(f, "Unexpected character '{}' on line {}", c, line)
             }
-            ScannerError::UnterminatedString(line) => {
+            ScannerError::UnterminatedString(line, _) => {
                 write!(f, "Unterminated string on line {}", line)
             }
-            ScannerError::UnterminatedEscape(line) => {
+            ScannerError::UnterminatedEscape(line, _) => {
                 write!(f, "Unterminated escape sequence on line {}", line)
             }
-            ScannerError::UnterminatedComment(line) => {
+            ScannerError::UnterminatedComment(line, _) => {
                 write!(f, "Unterminated comment on line {}", line)
             }
         }
@@ -295,6 +560,10 @@ impl fmt::Display for ScannerError {
 }
 
 impl std::error::Error for ScannerError {}
+impl std::error::Error for ParserError<'_> {}
+impl std::error::Error for CompilerError<'_> {}
+impl std::error::Error for RuntimeError<'_> {}
+impl std::error::Error for LoxError<'_> {}
 
 impl From<io::Error> for ScannerError {
     fn from(e: io::Error) -> Self {
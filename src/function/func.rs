@@ -5,6 +5,7 @@ use crate::callable::Callable;
 use crate::error::RuntimeError;
 use crate::{
     ast::stmt::FunctionDecl,
+    class::LoxClass,
     environment::{Environment, SharedEnv},
     instance::LoxInstance,
     interpreter::{Interpreter, Value},
@@ -19,17 +20,13 @@ pub struct Function<'source> {
 }
 
 impl<'source> Function<'source> {
+    // A plain function or method. Only a class's own `init` is an initializer, so static
+    // methods, getters and setters named `init` are built here too.
     pub fn new(declaration: FunctionDecl<'source>, closure: SharedEnv<'source>) -> Self {
-        let is_initializer = declaration
-            .name
-            .as_ref()
-            .map(|name| name.lexeme == "init")
-            .unwrap_or(false);
-
         Self {
             declaration,
             closure,
-            is_initializer,
+            is_initializer: false,
         }
     }
 
@@ -45,9 +42,17 @@ impl<'source> Function<'source> {
     }
 
     pub fn bind(&self, instance: Rc<RefCell<LoxInstance<'source>>>) -> Function<'source> {
+        self.bind_this(Value::Instance(instance))
+    }
+
+    // Static methods see the class they were looked up on as `this`
+    pub fn bind_class(&self, class: Rc<LoxClass<'source>>) -> Function<'source> {
+        self.bind_this(Value::Class(class))
+    }
+
+    fn bind_this(&self, this: Value<'source>) -> Function<'source> {
         let env = Environment::from_enclosing(self.closure.clone());
-        env.borrow_mut()
-            .define("this".to_string(), Value::Instance(instance));
+        env.borrow_mut().define("this".to_string(), this);
 
         Function {
             declaration: self.declaration.clone(),
@@ -65,7 +70,7 @@ impl<'source> Callable<'source> for Function<'source> {
     ) -> Result<Value<'source>, RuntimeError<'source>> {
         let env = Environment::from_enclosing(self.closure.clone());
 
-        for (param, arg) in self.declaration.params.iter().zip(args.into_iter()) {
+        for (param, arg) in self.declaration.params.iter().zip(args) {
             env.borrow_mut().define(param.lexeme.to_string(), arg);
         }
 
@@ -107,6 +112,15 @@ impl<'source> Callable<'source> for Function<'source> {
     fn arity(&self) -> usize {
         self.declaration.params.len()
     }
+
+    fn name(&self) -> String {
+        self.declaration
+            .name
+            .as_ref()
+            .map(|t| t.lexeme)
+            .unwrap_or("<anonymous>")
+            .to_string()
+    }
 }
 
 impl fmt::Debug for Function<'_> {
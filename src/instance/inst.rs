@@ -1,7 +1,10 @@
 // inst.rs
 // Implements LoxInstance, representing object instances in rlox.
 
-use crate::{class::LoxClass, error::RuntimeError, interpreter::interp::Value, token::Token};
+use crate::{
+    class::LoxClass, error::RuntimeError, function::Function, interpreter::interp::Value,
+    token::Token,
+};
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 #[derive(Debug, Clone)]
@@ -23,20 +26,69 @@ impl<'source> LoxInstance<'source> {
         instance: Rc<RefCell<LoxInstance<'source>>>,
         name: Token<'source>,
     ) -> Result<Value<'source>, RuntimeError<'source>> {
-        if let Some(value) = self.fields.get(name.lexeme) {
-            return Ok(value.clone());
-        }
-
-        if let Some(method) = self.klass.find_method(name.lexeme) {
-            return Ok(Value::Callable(Rc::new(method.bind(instance.clone()))));
+        if let Some(value) = self.property(instance, name.lexeme) {
+            return Ok(value);
         }
 
         Err(RuntimeError::TypeError {
-            msg: format!("Undefined property {}.", name.lexeme),
+            msg: format!("Undefined property '{}'.", name.lexeme),
             line: name.line,
+            span: name.span,
         })
     }
 
+    // A field, or else a method bound to `instance`, for lookups by name that don't come
+    // from a `.name` in the source (like the interpreter asking for `iter`)
+    pub fn property(
+        &self,
+        instance: Rc<RefCell<LoxInstance<'source>>>,
+        name: &str,
+    ) -> Option<Value<'source>> {
+        if let Some(value) = self.fields.get(name) {
+            return Some(value.clone());
+        }
+
+        self.klass
+            .find_method(name)
+            .map(|method| Value::Callable(Rc::new(method.bind(instance))))
+    }
+
+    // A method bound to `instance`, skipping fields. The interpreter finds special methods such
+    // as `__add` this way, so a field can't stand in for one.
+    pub fn method(
+        &self,
+        instance: Rc<RefCell<LoxInstance<'source>>>,
+        name: &str,
+    ) -> Option<Function<'source>> {
+        self.klass
+            .find_method(name)
+            .map(|method| method.bind(instance))
+    }
+
+    // The getter `name` bound to `instance`, unless a field of that name shadows it
+    pub fn getter(
+        &self,
+        instance: Rc<RefCell<LoxInstance<'source>>>,
+        name: &str,
+    ) -> Option<Function<'source>> {
+        if self.fields.contains_key(name) {
+            return None;
+        }
+        self.klass
+            .find_getter(name)
+            .map(|getter| getter.bind(instance))
+    }
+
+    pub fn setter(
+        &self,
+        instance: Rc<RefCell<LoxInstance<'source>>>,
+        name: &str,
+    ) -> Option<Function<'source>> {
+        self.klass
+            .find_setter(name)
+            .map(|setter| setter.bind(instance))
+    }
+
     pub fn set(&mut self, name: Token<'source>, value: Value<'source>) {
         self.fields.insert(name.lexeme.to_string(), value);
     }
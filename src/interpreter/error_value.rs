@@ -0,0 +1,26 @@
+// error_value.rs
+// Defines the value a `catch` clause receives when it catches one of the interpreter's own errors.
+
+use std::fmt;
+
+// A runtime error turned into a Lox value. Scripts read it through `e.message` and `e.line`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorValue {
+    pub message: String,
+    pub line: Option<usize>, // None when the failing code has no location, like `this` lookups
+}
+
+impl ErrorValue {
+    pub fn new(message: impl Into<String>, line: Option<usize>) -> Self {
+        Self {
+            message: message.into(),
+            line,
+        }
+    }
+}
+
+impl fmt::Display for ErrorValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
@@ -0,0 +1,30 @@
+// frame.rs
+// Defines the call frames the interpreter keeps so uncaught runtime errors can show a traceback.
+
+use crate::token::Span;
+
+// The line of calls a Rust host makes through `Lox::call_function`, which have no call site
+pub const HOST_LINE: usize = 0;
+
+// The span of those calls. No source reaches this far, so errors raised at the host's call
+// site can be told apart from errors that merely have no line of their own.
+pub const HOST_SPAN: Span = Span {
+    start: usize::MAX,
+    end: usize::MAX,
+};
+
+// One active call: which function is running and where it was called from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallFrame {
+    pub function: String,
+    pub line: usize, // line of the call's closing paren
+}
+
+impl CallFrame {
+    pub fn new(function: impl Into<String>, line: usize) -> Self {
+        Self {
+            function: function.into(),
+            line,
+        }
+    }
+}
@@ -4,28 +4,56 @@
 use crate::{
     ast::{
         expr::Expr,
-        stmt::{FunctionDecl, Stmt},
+        stmt::{CatchClause, FunctionDecl, Stmt},
     },
-    callable::{Callable, Clock},
-    class::LoxClass,
+    callable::{self, Callable, NativeFunction, list_index},
+    class::{Accessors, LoxClass, LoxTrait},
+    diagnostic::Diagnostic,
     environment::env::{Environment, SharedEnv},
     error::RuntimeError,
     function::Function,
     instance::LoxInstance,
-    token::{Literal, Token, TokenType},
+    interpreter::{CallFrame, ErrorValue, LoxIterator, MapKey, Range, SharedMap},
+    token::{Literal, Span, Token, TokenType},
 };
 use by_address::ByAddress;
 use core::fmt;
+use indexmap::IndexMap;
 use std::collections::HashMap;
+use std::io::{self, Write};
 use std::{cell::RefCell, rc::Rc};
 
+// Deepest call nesting allowed by default before a "Stack overflow" error is raised
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 10_000;
+
+// The host stack is grown in chunks of this size whenever less than RED_ZONE is left,
+// so the depth limit above, not the size of the Rust stack, decides how deep Lox can recurse
+const RED_ZONE: usize = 128 * 1024;
+const STACK_CHUNK: usize = 4 * 1024 * 1024;
+
 type ExprRef<'source> = Rc<Expr<'source>>;
 type ExprKey<'source> = ByAddress<ExprRef<'source>>;
 
+// Lists are shared, so every variable holding one sees changes made through the others
+pub type SharedList<'source> = Rc<RefCell<Vec<Value<'source>>>>;
+
+// What `object[index]` refers to once both sides have been evaluated and checked
+enum IndexTarget<'source> {
+    List(SharedList<'source>, usize),
+    Map(SharedMap<'source>, MapKey),
+    // Handed to the instance's `__index` or `__setindex` method
+    Instance(Rc<RefCell<LoxInstance<'source>>>, Value<'source>),
+}
+
 pub struct Interpreter<'source> {
     pub globals: SharedEnv<'source>,
     pub environment: SharedEnv<'source>,
     pub locals: HashMap<ExprKey<'source>, usize>,
+    pub call_stack: Vec<CallFrame>, // innermost call last
+    pub max_call_depth: usize,
+    pub output: Box<dyn Write + 'source>, // where `print` writes, stdout by default
+    pub error_output: Box<dyn Write + 'source>, // where diagnostics go, stderr by default
+    traceback: Vec<CallFrame>,            // the call stack as it was when the last error was raised
 }
 
 #[derive(Debug, Clone)]
@@ -36,7 +64,12 @@ pub enum Value<'source> {
     Nil,
     Callable(Rc<dyn Callable<'source> + 'source>),
     Class(Rc<LoxClass<'source>>),
+    Trait(Rc<LoxTrait<'source>>),
     Instance(Rc<RefCell<LoxInstance<'source>>>),
+    List(SharedList<'source>),
+    Map(SharedMap<'source>),
+    Range(Range),
+    Error(Rc<ErrorValue>),
 }
 
 impl PartialEq for Value<'_> {
@@ -50,6 +83,12 @@ impl PartialEq for Value<'_> {
             // Callable values are never equal
             (Callable(_), Callable(_)) => false,
             (Class(_), Class(_)) => false,
+            (Trait(_), Trait(_)) => false,
+            // Lists compare by identity, like instances in other Lox implementations
+            (List(a), List(b)) => Rc::ptr_eq(a, b),
+            (Map(a), Map(b)) => Rc::ptr_eq(a, b),
+            (Range(a), Range(b)) => a == b,
+            (Error(a), Error(b)) => Rc::ptr_eq(a, b),
             _ => false,
         }
     }
@@ -65,15 +104,55 @@ impl<'source> Default for Interpreter<'source> {
 impl<'source> Interpreter<'source> {
     pub fn new() -> Self {
         let globals = Environment::new();
-        globals
-            .borrow_mut()
-            .define("clock".to_string(), Value::Callable(Rc::new(Clock)));
 
-        Interpreter {
+        let mut interpreter = Interpreter {
             globals: globals.clone(),
             environment: globals,
             locals: HashMap::new(),
-        }
+            call_stack: Vec::new(),
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            output: Box::new(io::stdout()),
+            error_output: Box::new(io::stderr()),
+            traceback: Vec::new(),
+        };
+        interpreter.define_native("clock", 0, callable::clock);
+        interpreter.define_native("len", 1, callable::len);
+        interpreter.define_native("push", 2, callable::push);
+        interpreter.define_native("pop", 1, callable::pop);
+        interpreter.define_native("insert", 3, callable::insert);
+        interpreter.define_native("remove", 2, callable::remove);
+        interpreter.define_native("slice", 3, callable::slice);
+        interpreter.define_native("range", 3, callable::range);
+        interpreter.define_native("keys", 1, callable::keys);
+        interpreter.define_native("values", 1, callable::values);
+        interpreter.define_native("has", 2, callable::has);
+        interpreter.define_native("delete", 2, callable::delete);
+        interpreter
+    }
+
+    // Exposes a Rust closure to Lox as a global function. The closure only runs with exactly
+    // `arity` arguments; errors it returns (e.g. from `number_arg` or `"message".into()`)
+    // are reported at the call site like any other runtime error.
+    pub fn define_native<F>(&mut self, name: &str, arity: usize, function: F)
+    where
+        F: Fn(
+                &mut Interpreter<'source>,
+                Vec<Value<'source>>,
+            ) -> Result<Value<'source>, RuntimeError<'source>>
+            + 'source,
+    {
+        let native = NativeFunction::new(name, arity, function);
+        self.globals
+            .borrow_mut()
+            .define(name.to_string(), Value::Callable(Rc::new(native)));
+    }
+
+    // Hands over the frames that were active when the last uncaught error was raised,
+    // innermost first, and forgets them so they don't leak into the next error.
+    pub fn take_traceback(&mut self) -> Vec<CallFrame> {
+        let mut frames = std::mem::take(&mut self.traceback);
+        frames.reverse();
+        frames
     }
 
     pub fn interpret(&mut self, statements: &[Stmt<'source>]) -> Result<(), RuntimeError<'source>> {
@@ -88,8 +167,10 @@ impl<'source> Interpreter<'source> {
         expr: Rc<Expr<'source>>,
     ) -> Result<Value<'source>, RuntimeError<'source>> {
         match &*expr {
-            Expr::Lambda { params, body } => self.evaluate_lambda(params.clone(), body.clone()),
-            Expr::Literal(lit) => self.evaluate_literal(lit.clone()),
+            Expr::Lambda { params, body, span } => {
+                self.evaluate_lambda(params.clone(), body.clone(), *span)
+            }
+            Expr::Literal(lit, _) => self.evaluate_literal(lit.clone()),
             Expr::Unary { operator, right } => {
                 self.evaluate_unary(operator.clone(), &right.clone())
             }
@@ -136,7 +217,7 @@ impl<'source> Interpreter<'source> {
                     self.globals.borrow().get(keyword)
                 }
             }
-            Expr::Grouping(inner) => self.evaluate(inner.clone()),
+            Expr::Grouping(inner, _) => self.evaluate(inner.clone()),
             Expr::Ternary {
                 condition,
                 true_expr,
@@ -147,6 +228,80 @@ impl<'source> Interpreter<'source> {
                 operator,
                 right,
             } => self.evaluate_logical(left.clone(), operator, right.clone()),
+            Expr::List { elements, .. } => {
+                let mut values = Vec::with_capacity(elements.len());
+                for element in elements {
+                    values.push(self.evaluate(element.clone())?);
+                }
+                Ok(Value::List(Rc::new(RefCell::new(values))))
+            }
+            Expr::Map { brace, entries, .. } => {
+                let mut map = IndexMap::with_capacity(entries.len());
+                for (key, value) in entries {
+                    let key_val = self.evaluate(key.clone())?;
+                    let key = MapKey::new(&key_val).map_err(|msg| RuntimeError::TypeError {
+                        msg,
+                        line: brace.line,
+                        span: key.span(),
+                    })?;
+                    let value = self.evaluate(value.clone())?;
+                    map.insert(key, value);
+                }
+                Ok(Value::Map(Rc::new(RefCell::new(map))))
+            }
+            Expr::Index {
+                object,
+                index,
+                bracket,
+            } => match self.evaluate_index(object, index, bracket)? {
+                IndexTarget::List(list, position) => Ok(list.borrow()[position].clone()),
+                IndexTarget::Map(map, key) => {
+                    let value = map.borrow().get(&key).cloned();
+                    value.ok_or_else(|| RuntimeError::IndexError {
+                        msg: format!("Undefined key {}.", key),
+                        line: bracket.line,
+                        span: object.span().to(bracket.span),
+                    })
+                }
+                IndexTarget::Instance(instance, index) => {
+                    let span = object.span().to(bracket.span);
+                    let method = self.index_method(&instance, "__index", bracket, span)?;
+                    self.call_value(method, vec![index], bracket, span)
+                }
+            },
+            Expr::IndexSet {
+                object,
+                index,
+                bracket,
+                value,
+            } => {
+                let target = self.evaluate_index(object, index, bracket)?;
+                let value = self.evaluate(value.clone())?;
+                match target {
+                    IndexTarget::List(list, position) => {
+                        // The right-hand side may have shrunk the list since the index was checked
+                        let length = list.borrow().len();
+                        let position = list_index(position as f64, length).map_err(|msg| {
+                            RuntimeError::IndexError {
+                                msg,
+                                line: bracket.line,
+                                span: object.span().to(bracket.span),
+                            }
+                        })?;
+                        list.borrow_mut()[position] = value.clone()
+                    }
+                    // Assigning to a missing key adds it
+                    IndexTarget::Map(map, key) => {
+                        map.borrow_mut().insert(key, value.clone());
+                    }
+                    IndexTarget::Instance(instance, index) => {
+                        let span = object.span().to(bracket.span);
+                        let method = self.index_method(&instance, "__setindex", bracket, span)?;
+                        self.call_value(method, vec![index, value.clone()], bracket, span)?;
+                    }
+                }
+                Ok(value)
+            }
         }
     }
 
@@ -172,19 +327,26 @@ impl<'source> Interpreter<'source> {
 
     pub fn execute(&mut self, stmt: &Stmt<'source>) -> Result<(), RuntimeError<'source>> {
         match stmt {
-            Stmt::Block(statements) => {
+            Stmt::Block(statements, _) => {
                 let new_env = Environment::from_enclosing(self.environment.clone());
                 self.execute_block(statements, new_env)?;
                 Ok(())
             }
-            Stmt::Class {
-                name: _,
-                superclass: _,
-                methods: _,
-            } => {
+            Stmt::Class { .. } => {
                 let _value = self.evaluate_class(stmt.clone())?;
                 Ok(())
             }
+            Stmt::Trait { name, methods, .. } => {
+                let lox_trait = LoxTrait::new(
+                    name.lexeme.to_string(),
+                    methods.clone(),
+                    self.environment.clone(),
+                );
+                self.environment
+                    .borrow_mut()
+                    .define(name.lexeme.to_string(), Value::Trait(Rc::new(lox_trait)));
+                Ok(())
+            }
             Stmt::Expression(expr) => {
                 let _value = self.evaluate(expr.clone())?;
                 Ok(())
@@ -194,14 +356,15 @@ impl<'source> Interpreter<'source> {
                 condition,
                 then_branch,
                 else_branch,
+                ..
             } => {
                 self.evaluate_if_statement(condition.clone(), then_branch, else_branch.as_deref())?;
                 Ok(())
             }
-            Stmt::Print(expr) => {
-                let value = self.evaluate(expr.clone())?;
-                println!("{}", value);
-                Ok(())
+            Stmt::Print { keyword, value, .. } => {
+                let value = self.evaluate(value.clone())?;
+                let text = self.stringify_at(&value, keyword)?;
+                writeln!(self.output, "{}", text).map_err(RuntimeError::Io)
             }
             Stmt::Return { keyword: _, value } => {
                 let result = match value {
@@ -210,16 +373,52 @@ impl<'source> Interpreter<'source> {
                 };
                 Err(RuntimeError::ReturnException(result))
             }
-            Stmt::While { condition, body } => {
-                self.evaluate_while(condition.clone(), body)?;
+            Stmt::While {
+                condition,
+                body,
+                increment,
+                label,
+                ..
+            } => {
+                let label = label.as_ref().map(|label| label.lexeme);
+                self.evaluate_while(condition.clone(), body, increment.clone(), label)?;
                 Ok(())
             }
-            Stmt::Break { keyword: _ } => {
-                self.evaluate_break()?;
+            Stmt::ForIn {
+                name,
+                iterable,
+                body,
+                label,
+                ..
+            } => {
+                let label = label.as_ref().map(|label| label.lexeme);
+                self.evaluate_for_in(name, iterable, body, label)
+            }
+            Stmt::Try {
+                body,
+                catch,
+                finally,
+                ..
+            } => self.evaluate_try(body, catch.as_ref(), finally.as_deref()),
+            Stmt::Throw { keyword, value } => {
+                let value = self.evaluate(value.clone())?;
+                Err(RuntimeError::Thrown {
+                    value,
+                    line: keyword.line,
+                    span: stmt.span(),
+                })
+            }
+            Stmt::Break { label, .. } => {
+                self.evaluate_break(label.as_ref().map(|label| label.lexeme))?;
                 Ok(())
             }
+            Stmt::Continue { label, .. } => Err(RuntimeError::ContinueException(
+                label.as_ref().map(|label| label.lexeme),
+            )),
             // In jlox, you can define unitialized variables but if you use them they'll just be nil
-            Stmt::Var { name, initializer } => {
+            Stmt::Var {
+                name, initializer, ..
+            } => {
                 self.evaluate_var_decl(name.clone(), initializer.clone())?;
                 Ok(())
             }
@@ -251,8 +450,14 @@ impl<'source> Interpreter<'source> {
         if let Stmt::Class {
             name,
             superclass,
+            traits,
             methods,
-        } = class
+            static_methods,
+            static_fields,
+            getters,
+            setters,
+            ..
+        } = &class
         {
             self.environment
                 .borrow_mut()
@@ -260,72 +465,153 @@ impl<'source> Interpreter<'source> {
 
             let mut super_class_value: Option<Rc<LoxClass<'source>>> = None;
             if let Some(super_expr) = &superclass {
-                let eval = self.evaluate(super_expr.clone())?;
-                match eval {
-                    Value::Class(class_obj) => {
-                        super_class_value = Some(class_obj.clone());
-
-                        let new_env: Rc<RefCell<Environment>> =
-                            Environment::from_enclosing(self.environment.clone());
-                        Environment::define(
-                            &mut new_env.borrow_mut(),
-                            "super".to_string(),
-                            Value::Class(class_obj.clone()),
-                        );
-                        self.environment = new_env;
-                    }
+                match self.evaluate(super_expr.clone())? {
+                    Value::Class(class_obj) => super_class_value = Some(class_obj),
                     _ => {
                         return Err(RuntimeError::TypeError {
                             msg: "Superclass must be a class.".to_string(),
                             line: name.line,
+                            span: super_expr.span(),
                         });
                     }
                 }
             }
-            let mut method_map: HashMap<String, Function<'source>> = HashMap::new();
+            // Everything that can fail runs before the environment is switched below, so an
+            // error can't leave the session stuck in the class's `super` scope
+            let mut method_map =
+                self.trait_methods(name, traits, super_class_value.as_ref(), methods)?;
+
+            let enclosing = self.environment.clone();
+            if let Some(class_obj) = &super_class_value {
+                let new_env: Rc<RefCell<Environment>> =
+                    Environment::from_enclosing(self.environment.clone());
+                Environment::define(
+                    &mut new_env.borrow_mut(),
+                    "super".to_string(),
+                    Value::Class(class_obj.clone()),
+                );
+                self.environment = new_env;
+            }
             for method in methods {
                 let function = if method.name.as_ref().map(|name| name.lexeme) == Some("init") {
                     Function::new_initializer(method.clone(), self.environment.clone())
                 } else {
                     Function::new(method.clone(), self.environment.clone())
                 };
-                if let Some(method_name) = method.name {
+                if let Some(method_name) = &method.name {
                     method_map.insert(method_name.lexeme.to_string(), function);
                 }
             }
-            let klass = LoxClass::new(name.lexeme.to_string(), method_map, super_class_value);
+            let static_map = self.method_map(static_methods);
+            let accessors = Accessors {
+                getters: self.method_map(getters),
+                setters: self.method_map(setters),
+            };
+            self.environment = enclosing;
+
+            let klass = Rc::new(LoxClass::new(
+                name.lexeme.to_string(),
+                method_map,
+                static_map,
+                accessors,
+                super_class_value,
+            ));
             self.environment
                 .borrow_mut()
-                .assign(name, &Value::Class(klass.into()))?;
+                .assign(name.clone(), &Value::Class(klass.clone()))?;
 
-            if superclass.is_some() {
-                let enclosing_env = {
-                    let env_ref = self.environment.borrow();
-                    env_ref.enclosing.clone()
+            // Run after the class is defined, so `static origin = Point(0, 0);` works
+            for field in static_fields {
+                let value = match &field.initializer {
+                    Some(initializer) => self.evaluate(initializer.clone())?,
+                    None => Value::Nil,
                 };
-                if let Some(enclosing) = enclosing_env {
-                    self.environment = enclosing;
-                }
+                klass.set_static(field.name.lexeme, value);
             }
             Ok(Value::Nil)
         } else {
             Err(RuntimeError::TypeError {
                 msg: "Expected class statement".to_string(),
                 line: 0,
+                span: class.span(),
             })
         }
     }
 
+    // Methods mixed in from `with A, B`. A method the class defines itself replaces the trait's
+    // version, but two traits providing the same method leave no way to pick one.
+    fn trait_methods(
+        &mut self,
+        class_name: &Token<'source>,
+        traits: &[Rc<Expr<'source>>],
+        superclass: Option<&Rc<LoxClass<'source>>>,
+        own_methods: &[FunctionDecl<'source>],
+    ) -> Result<HashMap<String, Function<'source>>, RuntimeError<'source>> {
+        let mut mixed_in: HashMap<String, (Function<'source>, Rc<LoxTrait<'source>>)> =
+            HashMap::new();
+        for trait_expr in traits {
+            let lox_trait = match self.evaluate(trait_expr.clone())? {
+                Value::Trait(lox_trait) => lox_trait,
+                other => {
+                    return Err(RuntimeError::TypeError {
+                        msg: format!("Can only mix in traits, got {}.", other.type_name()),
+                        line: class_name.line,
+                        span: trait_expr.span(),
+                    });
+                }
+            };
+            for (method_name, function) in lox_trait.methods_for(superclass) {
+                let overridden = own_methods.iter().any(|method| {
+                    method.name.as_ref().map(|name| name.lexeme) == Some(&method_name)
+                });
+                if overridden {
+                    continue;
+                }
+                if let Some((_, first)) = mixed_in.get(&method_name) {
+                    return Err(RuntimeError::TypeError {
+                        msg: format!(
+                            "Method '{}' is defined by both traits {} and {}.",
+                            method_name, first.name, lox_trait.name
+                        ),
+                        line: class_name.line,
+                        span: class_name.span,
+                    });
+                }
+                mixed_in.insert(method_name, (function, lox_trait.clone()));
+            }
+        }
+        Ok(mixed_in
+            .into_iter()
+            .map(|(method_name, (function, _))| (method_name, function))
+            .collect())
+    }
+
+    // Plain functions closing over the current environment, keyed by name
+    fn method_map(&self, methods: &[FunctionDecl<'source>]) -> HashMap<String, Function<'source>> {
+        methods
+            .iter()
+            .filter_map(|method| {
+                let name = method.name.as_ref()?.lexeme.to_string();
+                Some((
+                    name,
+                    Function::new(method.clone(), self.environment.clone()),
+                ))
+            })
+            .collect()
+    }
+
     fn evaluate_lambda(
         &mut self,
         paramaters: Vec<Token<'source>>,
         body_block: Vec<Stmt<'source>>,
+        span: Span,
     ) -> Result<Value<'source>, RuntimeError<'source>> {
         let function = Function {
             declaration: FunctionDecl {
                 name: None,
                 params: paramaters,
                 body: body_block,
+                span,
             },
             closure: self.environment.clone(),
             is_initializer: false,
@@ -353,22 +639,98 @@ impl<'source> Interpreter<'source> {
         &mut self,
         cond: Rc<Expr<'source>>,
         body: &Stmt<'source>,
+        increment: Option<Rc<Expr<'source>>>,
+        label: Option<&'source str>,
     ) -> Result<Value<'source>, RuntimeError<'source>> {
+        // An unlabeled `break`/`continue` targets the innermost loop, a labeled one
+        // keeps unwinding until it reaches the loop with that label
+        let targets_this = |target: Option<&str>| target.is_none() || target == label;
         while {
             let cond_val = self.evaluate(cond.clone())?;
             self.is_truthy(&cond_val)
         } {
             match self.execute(body) {
-                Err(RuntimeError::BreakException) => break,
+                Err(RuntimeError::BreakException(target)) if targets_this(target) => break,
+                Err(RuntimeError::ContinueException(target)) if targets_this(target) => {}
                 Err(e) => return Err(e),
                 _ => {}
             }
+            if let Some(increment) = &increment {
+                self.evaluate(increment.clone())?;
+            }
         }
         Ok(Value::Nil)
     }
 
-    fn evaluate_break(&mut self) -> Result<(), RuntimeError<'source>> {
-        Err(RuntimeError::BreakException)
+    fn evaluate_for_in(
+        &mut self,
+        name: &Token<'source>,
+        iterable: &Rc<Expr<'source>>,
+        body: &Stmt<'source>,
+        label: Option<&'source str>,
+    ) -> Result<(), RuntimeError<'source>> {
+        let span = iterable.span();
+        let value = self.evaluate(iterable.clone())?;
+        let mut iterator = LoxIterator::new(self, value, name, span)?;
+        let targets_this = |target: Option<&str>| target.is_none() || target == label;
+
+        while let Some(element) = iterator.next(self, name, span)? {
+            // A fresh variable per element, so closures created in the body keep their own
+            let env = Environment::from_enclosing(self.environment.clone());
+            env.borrow_mut().define(name.lexeme.to_string(), element);
+            match self.execute_block(std::slice::from_ref(body), env) {
+                Err(RuntimeError::BreakException(target)) if targets_this(target) => break,
+                Err(RuntimeError::ContinueException(target)) if targets_this(target) => {}
+                Err(e) => return Err(e),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn evaluate_try(
+        &mut self,
+        body: &[Stmt<'source>],
+        catch: Option<&CatchClause<'source>>,
+        finally: Option<&[Stmt<'source>]>,
+    ) -> Result<(), RuntimeError<'source>> {
+        let env = Environment::from_enclosing(self.environment.clone());
+        let mut result = self.execute_block(body, env);
+
+        if let Some(catch) = catch
+            && let Err(error) = &result
+            && error.is_catchable()
+        {
+            // The error is handled, so its call stack must not show up in a later traceback
+            self.traceback.clear();
+            let caught = match result.unwrap_err() {
+                RuntimeError::Thrown { value, .. } => value,
+                error => {
+                    let message = Diagnostic::from(&error).message;
+                    Value::Error(Rc::new(ErrorValue::new(message, error.line())))
+                }
+            };
+            let env = Environment::from_enclosing(self.environment.clone());
+            env.borrow_mut()
+                .define(catch.name.lexeme.to_string(), caught);
+            result = self.execute_block(&catch.body, env);
+        }
+
+        // `finally` runs however the rest ended, including through return, break and continue.
+        // If it ends abruptly itself, that replaces whatever was in flight.
+        if let Some(finally) = finally {
+            // Errors caught or raised inside `finally` must not touch the traceback of the
+            // error still in flight, so it is put aside and only restored if that error goes on
+            let pending_traceback = std::mem::take(&mut self.traceback);
+            let env = Environment::from_enclosing(self.environment.clone());
+            self.execute_block(finally, env)?;
+            self.traceback = pending_traceback;
+        }
+        result
+    }
+
+    fn evaluate_break(&mut self, label: Option<&'source str>) -> Result<(), RuntimeError<'source>> {
+        Err(RuntimeError::BreakException(label))
     }
 
     fn evaluate_if_statement(
@@ -453,12 +815,26 @@ impl<'source> Interpreter<'source> {
         match object {
             Value::Instance(instance) => {
                 let val = self.evaluate(value.clone())?;
-                instance.borrow_mut().set(name, val.clone());
+                let setter = instance.borrow().setter(instance.clone(), name.lexeme);
+                match setter {
+                    Some(setter) => {
+                        let span = name.span.to(value.span());
+                        let setter = Value::Callable(Rc::new(setter));
+                        self.call_value(setter, vec![val.clone()], &name, span)?;
+                    }
+                    None => instance.borrow_mut().set(name, val.clone()),
+                }
+                Ok(val)
+            }
+            Value::Class(class) => {
+                let val = self.evaluate(value.clone())?;
+                class.set_static(name.lexeme, val.clone());
                 Ok(val)
             }
             _ => Err(RuntimeError::TypeError {
                 msg: "Invalid set target.".to_string(),
                 line: name.line,
+                span: name.span,
             }),
         }
     }
@@ -475,36 +851,62 @@ impl<'source> Interpreter<'source> {
             return Err(RuntimeError::TypeError {
                 msg: "Undefined variable 'super'.".into(),
                 line: keyword.line,
+                span: keyword.span,
             });
         };
 
         let superclass =
             match Environment::get_at_string(self.environment.clone(), distance, "super")? {
                 Value::Class(class_rc) => class_rc.clone(),
+                // A trait method mixed into a class that has no superclass
+                Value::Nil => {
+                    return Err(RuntimeError::TypeError {
+                        msg: "Can't use 'super' in a class with no superclass.".into(),
+                        line: keyword.line,
+                        span: keyword.span,
+                    });
+                }
                 _ => {
                     return Err(RuntimeError::TypeError {
                         msg: "super must be a class.".into(),
                         line: keyword.line,
+                        span: keyword.span,
                     });
                 }
             };
 
-        let object =
+        // `this` is the class itself inside a static method, where `super` finds static methods.
+        // An inherited getter runs right away, just like `this.name` would run it.
+        let bound =
             match Environment::get_at_string(self.environment.clone(), distance - 1, "this")? {
-                Value::Instance(instance_rc) => instance_rc.clone(),
+                Value::Instance(instance) => {
+                    if let Some(getter) = superclass.find_getter(method.lexeme) {
+                        let getter = Value::Callable(Rc::new(getter.bind(instance)));
+                        return self.call_value(getter, Vec::new(), &method, method.span);
+                    }
+                    superclass
+                        .find_method(method.lexeme)
+                        .map(|method_fn| method_fn.bind(instance))
+                }
+                Value::Class(class) => superclass
+                    .find_static_method(method.lexeme)
+                    .map(|method_fn| method_fn.bind_class(class)),
                 _ => {
                     return Err(RuntimeError::TypeError {
                         msg: "'this' must be instance.".into(),
                         line: keyword.line,
+                        span: keyword.span,
                     });
                 }
             };
 
-        if let Some(method_fn) = superclass.find_method(method.lexeme) {
-            Ok(Value::Callable(Rc::new(method_fn.bind(object))))
+        if let Some(method_fn) = bound {
+            Ok(Value::Callable(Rc::new(method_fn)))
         } else {
-            Err(RuntimeError::UndefinedVariable {
-                found: method.lexeme.to_string(),
+            Err(RuntimeError::TypeError {
+                msg: format!("Undefined property '{}'.", method.lexeme),
+                line: method.line,
+                span: method.span,
             })
         }
     }
@@ -522,6 +924,7 @@ impl<'source> Interpreter<'source> {
                 _ => Err(RuntimeError::UnaryMinus {
                     lexeme: operator.lexeme.to_string(),
                     line: operator.line,
+                    span: operator.span.to(right.span()),
                 }),
             },
             TokenType::Bang => Ok(Value::Bool(!self.is_truthy(&right_val))),
@@ -541,6 +944,7 @@ impl<'source> Interpreter<'source> {
                 return Err(RuntimeError::MutationError {
                     lexeme: operator.lexeme.to_string(),
                     line: operator.line,
+                    span: operand.span().to(operator.span),
                 });
             }
         };
@@ -576,6 +980,7 @@ impl<'source> Interpreter<'source> {
             _ => Err(RuntimeError::MutationError {
                 lexeme: operator.to_string(),
                 line: operator.line,
+                span: operand.span().to(operator.span),
             }),
         }
     }
@@ -583,13 +988,31 @@ impl<'source> Interpreter<'source> {
     fn evaluate_binary(
         &mut self,
         left: Rc<Expr<'source>>,
-        operator: &Token,
+        operator: &Token<'source>,
         right: Rc<Expr<'source>>,
     ) -> Result<Value<'source>, RuntimeError<'source>> {
         let left_val = self.evaluate(left.clone())?;
         let right_val = self.evaluate(right.clone())?;
         let lexeme = operator.lexeme.to_string();
         let line = operator.line;
+        let span = left.span().to(right.span());
+
+        // An instance on either side hands the operator to its class: `a + b` is `a.__add(b)`,
+        // or `b.__radd(a)` when only the right operand defines a method for it
+        if let Some((method_name, reflected_name)) = operator_method(operator.kind)
+            && let Some((method, argument)) = match special_method(&left_val, method_name) {
+                Some(method) => Some((method, right_val.clone())),
+                None => special_method(&right_val, reflected_name)
+                    .map(|method| (method, left_val.clone())),
+            }
+        {
+            let result = self.call_value(method, vec![argument], operator, span)?;
+            return Ok(match operator.kind {
+                TokenType::EqualEqual => Value::Bool(self.is_truthy(&result)),
+                TokenType::BangEqual => Value::Bool(!self.is_truthy(&result)),
+                _ => result,
+            });
+        }
 
         match operator.kind {
             TokenType::Comma => {
@@ -601,41 +1024,41 @@ impl<'source> Interpreter<'source> {
                 (Value::String(l), Value::String(r)) => Ok(Value::String(l + &r)),
                 (Value::String(l), Value::Number(r)) => Ok(Value::String(l + &r.to_string())),
                 (Value::Number(l), Value::String(r)) => Ok(Value::String(l.to_string() + &r)),
-                _ => Err(RuntimeError::BinaryPlus { lexeme, line }),
+                _ => Err(RuntimeError::BinaryPlus { lexeme, line, span }),
             },
             TokenType::Minus => match (left_val, right_val) {
                 (Value::Number(l), Value::Number(r)) => Ok(Value::Number(l - r)),
-                _ => Err(RuntimeError::BinaryMinus { lexeme, line }),
+                _ => Err(RuntimeError::BinaryMinus { lexeme, line, span }),
             },
             TokenType::Star => match (left_val, right_val) {
                 (Value::Number(l), Value::Number(r)) => Ok(Value::Number(l * r)),
-                _ => Err(RuntimeError::BinaryMult { lexeme, line }),
+                _ => Err(RuntimeError::BinaryMult { lexeme, line, span }),
             },
             TokenType::Slash => match (left_val, right_val) {
                 (Value::Number(l), Value::Number(r)) => {
                     if r == 0.0 {
-                        return Err(RuntimeError::BinaryDBZ { line });
+                        return Err(RuntimeError::BinaryDBZ { line, span });
                     }
                     Ok(Value::Number(l / r))
                 }
-                _ => Err(RuntimeError::BinaryDiv { lexeme, line }),
+                _ => Err(RuntimeError::BinaryDiv { lexeme, line, span }),
             },
             TokenType::EqualEqual => Ok(Value::Bool(left_val == right_val)),
             TokenType::Greater => match (left_val, right_val) {
                 (Value::Number(l), Value::Number(r)) => Ok(Value::Bool(l > r)),
-                _ => Err(RuntimeError::BinaryComp { lexeme, line }),
+                _ => Err(RuntimeError::BinaryComp { lexeme, line, span }),
             },
             TokenType::Less => match (left_val, right_val) {
                 (Value::Number(l), Value::Number(r)) => Ok(Value::Bool(l < r)),
-                _ => Err(RuntimeError::BinaryComp { lexeme, line }),
+                _ => Err(RuntimeError::BinaryComp { lexeme, line, span }),
             },
             TokenType::GreaterEqual => match (left_val, right_val) {
                 (Value::Number(l), Value::Number(r)) => Ok(Value::Bool(l >= r)),
-                _ => Err(RuntimeError::BinaryComp { lexeme, line }),
+                _ => Err(RuntimeError::BinaryComp { lexeme, line, span }),
             },
             TokenType::LessEqual => match (left_val, right_val) {
                 (Value::Number(l), Value::Number(r)) => Ok(Value::Bool(l <= r)),
-                _ => Err(RuntimeError::BinaryComp { lexeme, line }),
+                _ => Err(RuntimeError::BinaryComp { lexeme, line, span }),
             },
             TokenType::BangEqual => Ok(Value::Bool(left_val != right_val)),
             _ => unreachable!("Unknown binary operator"),
@@ -648,6 +1071,7 @@ impl<'source> Interpreter<'source> {
         paren: Token<'source>,
         args: Vec<Rc<Expr<'source>>>,
     ) -> Result<Value<'source>, RuntimeError<'source>> {
+        let span = callee.span().to(paren.span);
         let callee = self.evaluate(callee)?;
         let mut arguments: Vec<Value<'source>> = Vec::new();
 
@@ -655,34 +1079,73 @@ impl<'source> Interpreter<'source> {
             arguments.push(self.evaluate(argument)?);
         }
 
-        match callee {
-            Value::Callable(f) => {
-                if arguments.len() != f.arity() {
-                    return Err(RuntimeError::FunctionError {
-                        lexeme: paren.to_string(),
-                        line: paren.line,
-                        message: "Can only call functions and classes.".to_string(),
-                    });
-                }
-                f.call(self, arguments)
+        self.call_value(callee, arguments, &paren, span)
+    }
+
+    // Calls an already evaluated callee, checking its arity and keeping the call stack.
+    // `token` and `span` say where the call happens, for errors and tracebacks.
+    pub(crate) fn call_value(
+        &mut self,
+        callee: Value<'source>,
+        arguments: Vec<Value<'source>>,
+        token: &Token<'source>,
+        span: Span,
+    ) -> Result<Value<'source>, RuntimeError<'source>> {
+        let callee: Rc<dyn Callable<'source> + 'source> = match callee {
+            Value::Callable(f) => f,
+            Value::Class(class) => class,
+            _ => {
+                return Err(RuntimeError::FunctionError {
+                    lexeme: token.to_string(),
+                    line: token.line,
+                    span,
+                    message: "Can only call functions and classes.".to_string(),
+                });
             }
-            Value::Class(class) => {
-                if arguments.len() != class.arity() {
-                    return Err(RuntimeError::FunctionError {
-                        lexeme: paren.to_string(),
-                        line: paren.line,
-                        message: "Ensure your function call matches the function arity."
-                            .to_string(),
-                    });
-                }
-                class.call(self, arguments)
+        };
+
+        if arguments.len() != callee.arity() {
+            return Err(RuntimeError::FunctionError {
+                lexeme: token.to_string(),
+                line: token.line,
+                span,
+                message: format!(
+                    "Expected {} arguments but got {}.",
+                    callee.arity(),
+                    arguments.len()
+                ),
+            });
+        }
+
+        if self.call_stack.len() >= self.max_call_depth {
+            let error = RuntimeError::StackOverflow {
+                line: token.line,
+                span,
+            };
+            if self.traceback.is_empty() {
+                self.traceback = self.call_stack.clone();
             }
-            _ => Err(RuntimeError::FunctionError {
-                lexeme: paren.to_string(),
-                line: paren.line,
-                message: "Can only call functions and classes.".to_string(),
-            }),
+            return Err(error);
+        }
+
+        self.call_stack
+            .push(CallFrame::new(callee.name(), token.line));
+        let result = stacker::maybe_grow(RED_ZONE, STACK_CHUNK, || callee.call(self, arguments))
+            .map_err(|e| match e {
+                RuntimeError::NativeError(message) => RuntimeError::FunctionError {
+                    lexeme: token.to_string(),
+                    line: token.line,
+                    message: format!("{}: {}", callee.name(), message),
+                    span,
+                },
+                e => e,
+            });
+        // The innermost call sees the error first, so the outer ones leave its snapshot alone
+        if result.is_err() && self.traceback.is_empty() {
+            self.traceback = self.call_stack.clone();
         }
+        self.call_stack.pop();
+        result
     }
 
     fn evaluate_get(
@@ -693,16 +1156,199 @@ impl<'source> Interpreter<'source> {
         let object = self.evaluate(object_expr)?;
         match object {
             Value::Instance(instance) => {
+                let getter = instance.borrow().getter(instance.clone(), name.lexeme);
+                if let Some(getter) = getter {
+                    let getter = Value::Callable(Rc::new(getter));
+                    return self.call_value(getter, Vec::new(), &name, name.span);
+                }
                 // Don't drop the borrow too early
                 instance.borrow().get(instance.clone(), name)
             }
+            Value::Class(class) => {
+                class
+                    .property(class.clone(), name.lexeme)
+                    .ok_or_else(|| RuntimeError::TypeError {
+                        msg: format!("Undefined property '{}'.", name.lexeme),
+                        line: name.line,
+                        span: name.span,
+                    })
+            }
+            Value::Error(error) => match name.lexeme {
+                "message" => Ok(Value::String(error.message.clone())),
+                "line" => Ok(error
+                    .line
+                    .map_or(Value::Nil, |line| Value::Number(line as f64))),
+                _ => Err(RuntimeError::TypeError {
+                    msg: format!("Undefined property '{}'.", name.lexeme),
+                    line: name.line,
+                    span: name.span,
+                }),
+            },
             _ => Err(RuntimeError::TypeError {
                 msg: "Only instances have properties.".to_string(),
                 line: name.line,
+                span: name.span,
+            }),
+        }
+    }
+
+    // Evaluates the container and key of `object[index]`. List positions are bounds-checked here,
+    // whether a map key exists depends on whether it is being read or assigned.
+    fn evaluate_index(
+        &mut self,
+        object: &Rc<Expr<'source>>,
+        index: &Rc<Expr<'source>>,
+        bracket: &Token<'source>,
+    ) -> Result<IndexTarget<'source>, RuntimeError<'source>> {
+        let line = bracket.line;
+        let span = object.span().to(bracket.span);
+        let object = self.evaluate(object.clone())?;
+        let index = self.evaluate(index.clone())?;
+
+        match object {
+            Value::List(list) => {
+                let position = match index {
+                    Value::Number(n) => n,
+                    other => {
+                        return Err(RuntimeError::TypeError {
+                            msg: format!("List index must be a number, got {}.", other.type_name()),
+                            line,
+                            span,
+                        });
+                    }
+                };
+                let length = list.borrow().len();
+                let position = list_index(position, length)
+                    .map_err(|msg| RuntimeError::IndexError { msg, line, span })?;
+                Ok(IndexTarget::List(list, position))
+            }
+            Value::Map(map) => {
+                let key = MapKey::new(&index).map_err(|msg| RuntimeError::TypeError {
+                    msg,
+                    line,
+                    span,
+                })?;
+                Ok(IndexTarget::Map(map, key))
+            }
+            Value::Instance(instance) => Ok(IndexTarget::Instance(instance, index)),
+            other => Err(RuntimeError::TypeError {
+                msg: format!(
+                    "Only lists and maps can be indexed, got {}.",
+                    other.type_name()
+                ),
+                line,
+                span,
             }),
         }
     }
 
+    fn index_method(
+        &self,
+        instance: &Rc<RefCell<LoxInstance<'source>>>,
+        name: &str,
+        bracket: &Token<'source>,
+        span: Span,
+    ) -> Result<Value<'source>, RuntimeError<'source>> {
+        let value = Value::Instance(instance.clone());
+        special_method(&value, name).ok_or_else(|| RuntimeError::TypeError {
+            msg: format!(
+                "Only lists, maps and instances with a '{}' method can be indexed, got {} instance.",
+                name,
+                instance.borrow()
+            ),
+            line: bracket.line,
+            span,
+        })
+    }
+
+    // What `print` shows for a value. Instances whose class defines `__str` show the string it
+    // returns, also inside lists and maps. Errors from `__str` point at the method itself.
+    pub fn stringify(&mut self, value: &Value<'source>) -> Result<String, RuntimeError<'source>> {
+        self.stringify_from(value, None, &mut Vec::new())
+    }
+
+    // Like `stringify`, for printing done by the code at `site`, e.g. a `print` keyword
+    pub fn stringify_at(
+        &mut self,
+        value: &Value<'source>,
+        site: &Token<'source>,
+    ) -> Result<String, RuntimeError<'source>> {
+        self.stringify_from(value, Some(site), &mut Vec::new())
+    }
+
+    fn stringify_from(
+        &mut self,
+        value: &Value<'source>,
+        site: Option<&Token<'source>>,
+        printing: &mut Printing,
+    ) -> Result<String, RuntimeError<'source>> {
+        match value {
+            Value::Instance(instance) => {
+                let method = instance.borrow().method(instance.clone(), "__str");
+                let Some(method) = method else {
+                    return Ok(value.to_string());
+                };
+                let Some(site) = site.or(method.declaration.name.as_ref()).cloned() else {
+                    return Ok(value.to_string());
+                };
+                let method = Value::Callable(Rc::new(method));
+                match self.call_value(method, Vec::new(), &site, site.span)? {
+                    Value::String(s) => Ok(s),
+                    other => Err(RuntimeError::TypeError {
+                        msg: format!("'__str' must return a string, got {}.", other.type_name()),
+                        line: site.line,
+                        span: site.span,
+                    }),
+                }
+            }
+            Value::List(list) => {
+                if printing.contains(&identity(list)) {
+                    return Ok("[...]".to_string());
+                }
+                printing.push(identity(list));
+                let elements = list.borrow().clone();
+                let mut parts = Vec::with_capacity(elements.len());
+                for element in &elements {
+                    parts.push(self.stringify_element(element, site, printing)?);
+                }
+                printing.pop();
+                Ok(format!("[{}]", parts.join(", ")))
+            }
+            Value::Map(map) => {
+                if printing.contains(&identity(map)) {
+                    return Ok("{...}".to_string());
+                }
+                printing.push(identity(map));
+                let entries: Vec<_> = map
+                    .borrow()
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.clone()))
+                    .collect();
+                let mut parts = Vec::with_capacity(entries.len());
+                for (key, value) in &entries {
+                    let value = self.stringify_element(value, site, printing)?;
+                    parts.push(format!("{}: {}", key, value));
+                }
+                printing.pop();
+                Ok(format!("{{{}}}", parts.join(", ")))
+            }
+            _ => Ok(value.to_string()),
+        }
+    }
+
+    // Like `write_element`, strings inside lists and maps are quoted
+    fn stringify_element(
+        &mut self,
+        value: &Value<'source>,
+        site: Option<&Token<'source>>,
+        printing: &mut Printing,
+    ) -> Result<String, RuntimeError<'source>> {
+        match value {
+            Value::String(s) => Ok(format!("\"{}\"", s)),
+            _ => self.stringify_from(value, site, printing),
+        }
+    }
+
     fn evaluate_ternary(
         &mut self,
         condition: Rc<Expr<'source>>,
@@ -727,19 +1373,126 @@ impl<'source> Interpreter<'source> {
     }
 }
 
+impl Value<'_> {
+    // Name of the value's type as used in error messages
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::Number(_) => "number",
+            Value::Bool(_) => "boolean",
+            Value::Nil => "nil",
+            Value::Callable(_) => "function",
+            Value::Class(_) => "class",
+            Value::Trait(_) => "trait",
+            Value::Instance(_) => "instance",
+            Value::List(_) => "list",
+            Value::Map(_) => "map",
+            Value::Range(_) => "range",
+            Value::Error(_) => "error",
+        }
+    }
+}
+
 impl fmt::Display for Value<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Value::String(s) => write!(f, "{}", s),
-            Value::Number(n) => write!(f, "{}", n),
-            Value::Bool(b) => write!(f, "{}", b),
-            Value::Nil => write!(f, "nil"),
-            Value::Callable(c) => write!(f, "{:?}", c),
-            Value::Class(class) => write!(f, "{}", class),
-            Value::Instance(instance) => {
-                let borrowed = instance.borrow();
-                write!(f, "{} instance", borrowed)
+        write_value(f, self, &mut Vec::new())
+    }
+}
+
+// The lists and maps being printed, innermost last. One that contains itself is shown as
+// `[...]` or `{...}` when it comes up again, instead of being printed forever.
+type Printing = Vec<*const ()>;
+
+fn identity<T>(shared: &Rc<T>) -> *const () {
+    Rc::as_ptr(shared) as *const ()
+}
+
+fn write_value(f: &mut fmt::Formatter<'_>, value: &Value, printing: &mut Printing) -> fmt::Result {
+    match value {
+        Value::String(s) => write!(f, "{}", s),
+        Value::Number(n) => write!(f, "{}", n),
+        Value::Bool(b) => write!(f, "{}", b),
+        Value::Nil => write!(f, "nil"),
+        Value::Callable(c) => write!(f, "{:?}", c),
+        Value::Class(class) => write!(f, "{}", class),
+        Value::Trait(lox_trait) => write!(f, "{}", lox_trait),
+        Value::Instance(instance) => {
+            let borrowed = instance.borrow();
+            write!(f, "{} instance", borrowed)
+        }
+        Value::List(list) => {
+            if printing.contains(&identity(list)) {
+                return write!(f, "[...]");
+            }
+            printing.push(identity(list));
+            write!(f, "[")?;
+            for (i, element) in list.borrow().iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write_element(f, element, printing)?;
             }
+            printing.pop();
+            write!(f, "]")
         }
+        Value::Range(range) => write!(f, "{}", range),
+        Value::Error(error) => write!(f, "{}", error),
+        Value::Map(map) => {
+            if printing.contains(&identity(map)) {
+                return write!(f, "{{...}}");
+            }
+            printing.push(identity(map));
+            write!(f, "{{")?;
+            for (i, (key, value)) in map.borrow().iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}: ", key)?;
+                write_element(f, value, printing)?;
+            }
+            printing.pop();
+            write!(f, "}}")
+        }
+    }
+}
+
+// The methods a class defines to take over a binary operator: the first when its instance is on
+// the left, the second when it is only on the right. Comparisons mirror (`a < b` is `b.__gt(a)`),
+// and `!=` uses `__eq` and negates the result.
+fn operator_method(operator: TokenType) -> Option<(&'static str, &'static str)> {
+    match operator {
+        TokenType::Plus => Some(("__add", "__radd")),
+        TokenType::Minus => Some(("__sub", "__rsub")),
+        TokenType::Star => Some(("__mul", "__rmul")),
+        TokenType::Slash => Some(("__div", "__rdiv")),
+        TokenType::EqualEqual | TokenType::BangEqual => Some(("__eq", "__eq")),
+        TokenType::Less => Some(("__lt", "__gt")),
+        TokenType::LessEqual => Some(("__le", "__ge")),
+        TokenType::Greater => Some(("__gt", "__lt")),
+        TokenType::GreaterEqual => Some(("__ge", "__le")),
+        _ => None,
+    }
+}
+
+// The special method `name` bound to `value`, if it is an instance whose class defines one
+fn special_method<'source>(value: &Value<'source>, name: &str) -> Option<Value<'source>> {
+    match value {
+        Value::Instance(instance) => instance
+            .borrow()
+            .method(instance.clone(), name)
+            .map(|method| Value::Callable(Rc::new(method))),
+        _ => None,
+    }
+}
+
+// Strings inside lists and maps are quoted so that `["1", 1]` and `[1, 1]` print differently
+fn write_element(
+    f: &mut fmt::Formatter<'_>,
+    value: &Value,
+    printing: &mut Printing,
+) -> fmt::Result {
+    match value {
+        Value::String(s) => write!(f, "\"{}\"", s),
+        _ => write_value(f, value, printing),
     }
 }
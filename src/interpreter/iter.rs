@@ -0,0 +1,145 @@
+// iter.rs
+// Implements what `for (x in ...)` can loop over: lists, map keys, string characters, ranges and
+// instances following the iterator protocol.
+
+use std::{cell::RefCell, fmt, rc::Rc, vec};
+
+use crate::{
+    error::RuntimeError,
+    instance::LoxInstance,
+    interpreter::{Interpreter, SharedList, Value},
+    token::{Span, Token},
+};
+
+// The numbers from `start` towards `end` (exclusive) in steps of `step`, made by `range()`.
+// Elements are computed when asked for, so a range costs the same whatever its length.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Range {
+    pub start: f64,
+    pub end: f64,
+    pub step: f64,
+}
+
+impl Range {
+    pub fn new(start: f64, end: f64, step: f64) -> Result<Self, String> {
+        if step == 0.0 {
+            return Err("Step can't be zero.".to_string());
+        }
+        Ok(Self { start, end, step })
+    }
+
+    // The `n`th element, computed from the start so that steps like 0.1 don't drift
+    pub fn get(&self, n: usize) -> Option<f64> {
+        let value = self.start + n as f64 * self.step;
+        let inside = if self.step > 0.0 {
+            value < self.end
+        } else {
+            value > self.end
+        };
+        inside.then_some(value)
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "range({}, {}, {})", self.start, self.end, self.step)
+    }
+}
+
+// The state of one running for-in loop
+pub enum LoxIterator<'source> {
+    // Lists are read live, so elements pushed during the loop are visited too
+    List {
+        list: SharedList<'source>,
+        position: usize,
+    },
+    // Map keys and string characters are taken when the loop starts
+    Snapshot(vec::IntoIter<Value<'source>>),
+    Range {
+        range: Range,
+        position: usize,
+    },
+    // An object with a `next()` method, which returns nil once it is exhausted
+    Protocol(Rc<RefCell<LoxInstance<'source>>>),
+}
+
+impl<'source> LoxIterator<'source> {
+    // Instances are iterable through an `iter()` method returning the object to call `next()`
+    // on. An instance with only a `next()` method is its own iterator.
+    pub fn new(
+        interpreter: &mut Interpreter<'source>,
+        iterable: Value<'source>,
+        token: &Token<'source>,
+        span: Span,
+    ) -> Result<Self, RuntimeError<'source>> {
+        match iterable {
+            Value::List(list) => Ok(LoxIterator::List { list, position: 0 }),
+            Value::Map(map) => {
+                let keys: Vec<Value<'source>> = map.borrow().keys().map(|k| k.to_value()).collect();
+                Ok(LoxIterator::Snapshot(keys.into_iter()))
+            }
+            Value::String(s) => {
+                let chars: Vec<Value<'source>> =
+                    s.chars().map(|c| Value::String(c.to_string())).collect();
+                Ok(LoxIterator::Snapshot(chars.into_iter()))
+            }
+            Value::Range(range) => Ok(LoxIterator::Range { range, position: 0 }),
+            Value::Instance(instance) => {
+                let iter = instance.borrow().property(instance.clone(), "iter");
+                let iterator = match iter {
+                    Some(iter) => interpreter.call_value(iter, Vec::new(), token, span)?,
+                    None => Value::Instance(instance),
+                };
+                match iterator {
+                    Value::Instance(iterator)
+                        if iterator
+                            .borrow()
+                            .property(iterator.clone(), "next")
+                            .is_some() =>
+                    {
+                        Ok(LoxIterator::Protocol(iterator))
+                    }
+                    _ => Err(RuntimeError::TypeError {
+                        msg: "Iterators must be instances with a 'next' method.".to_string(),
+                        line: token.line,
+                        span,
+                    }),
+                }
+            }
+            other => Err(RuntimeError::TypeError {
+                msg: format!("Can't iterate over a {}.", other.type_name()),
+                line: token.line,
+                span,
+            }),
+        }
+    }
+
+    pub fn next(
+        &mut self,
+        interpreter: &mut Interpreter<'source>,
+        token: &Token<'source>,
+        span: Span,
+    ) -> Result<Option<Value<'source>>, RuntimeError<'source>> {
+        match self {
+            LoxIterator::List { list, position } => {
+                let element = list.borrow().get(*position).cloned();
+                *position += 1;
+                Ok(element)
+            }
+            LoxIterator::Snapshot(values) => Ok(values.next()),
+            LoxIterator::Range { range, position } => {
+                let element = range.get(*position).map(Value::Number);
+                *position += 1;
+                Ok(element)
+            }
+            LoxIterator::Protocol(iterator) => {
+                let next = iterator.borrow().property(iterator.clone(), "next");
+                let next = next.expect("checked when the iterator was created");
+                match interpreter.call_value(next, Vec::new(), token, span)? {
+                    Value::Nil => Ok(None),
+                    value => Ok(Some(value)),
+                }
+            }
+        }
+    }
+}
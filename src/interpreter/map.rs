@@ -0,0 +1,59 @@
+// map.rs
+// Defines the keys Lox maps can be indexed by and the shared map type behind `Value::Map`.
+
+use std::{cell::RefCell, fmt, rc::Rc};
+
+use indexmap::IndexMap;
+
+use crate::interpreter::Value;
+
+// Maps remember the order keys were first inserted in, so printing and `keys()` are predictable
+pub type SharedMap<'source> = Rc<RefCell<IndexMap<MapKey, Value<'source>>>>;
+
+// The hashable subset of values. Numbers are stored by their bits, with -0 folded into 0
+// because the two compare equal in Lox.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MapKey {
+    String(String),
+    Number(u64),
+    Bool(bool),
+    Nil,
+}
+
+impl MapKey {
+    pub fn new(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::String(s) => Ok(MapKey::String(s.clone())),
+            Value::Number(n) => Ok(MapKey::Number(
+                if *n == 0.0 { 0.0f64 } else { *n }.to_bits(),
+            )),
+            Value::Bool(b) => Ok(MapKey::Bool(*b)),
+            Value::Nil => Ok(MapKey::Nil),
+            other => Err(format!(
+                "Map keys must be strings, numbers, booleans or nil, got {}.",
+                other.type_name()
+            )),
+        }
+    }
+
+    pub fn to_value<'source>(&self) -> Value<'source> {
+        match self {
+            MapKey::String(s) => Value::String(s.clone()),
+            MapKey::Number(bits) => Value::Number(f64::from_bits(*bits)),
+            MapKey::Bool(b) => Value::Bool(*b),
+            MapKey::Nil => Value::Nil,
+        }
+    }
+}
+
+// Keys print the way they would inside a map literal, strings quoted
+impl fmt::Display for MapKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapKey::String(s) => write!(f, "\"{}\"", s),
+            MapKey::Number(bits) => write!(f, "{}", f64::from_bits(*bits)),
+            MapKey::Bool(b) => write!(f, "{}", b),
+            MapKey::Nil => write!(f, "nil"),
+        }
+    }
+}
@@ -1,5 +1,15 @@
 // mod.rs
 // Interpreter module entry point. Re-exports interpreter functionality.
 
+pub mod error_value;
+pub mod frame;
 pub mod interp;
+pub mod iter;
+pub mod map;
+pub mod output;
+pub use error_value::*;
+pub use frame::*;
 pub use interp::*;
+pub use iter::*;
+pub use map::*;
+pub use output::*;
@@ -4,11 +4,13 @@
 pub mod ast;
 pub mod callable;
 pub mod class;
+pub mod diagnostic;
 pub mod environment;
 pub mod error;
 pub mod function;
 pub mod instance;
 pub mod interpreter;
+pub mod lox;
 pub mod parser;
 pub mod resolver;
 pub mod scanner;
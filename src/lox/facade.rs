@@ -0,0 +1,315 @@
+// facade.rs
+// Implements `Lox`, a single entry point that runs the whole scan/parse/resolve/interpret pipeline.
+
+use std::{io::Write, rc::Rc};
+
+use crate::{
+    ast::stmt::Stmt,
+    callable::Callable,
+    diagnostic::{Diagnostic, uncaught_exception},
+    environment::Environment,
+    error::{LoxError, RuntimeError},
+    interpreter::{HOST_LINE, HOST_SPAN, Interpreter, Value},
+    parser::Parser,
+    resolver::Resolver,
+    scanner::Scanner,
+    token::{Span, Token, TokenType},
+};
+
+// Name of sources that were not given one, e.g. by `run_source`
+const UNNAMED: &str = "<script>";
+
+// An interpreter session for Rust hosts. Globals persist between calls, so a script can
+// define functions once and the host can call them or read its variables afterwards.
+//
+// Like the rest of the crate, values and definitions borrow from the source they came from,
+// so every source passed in must outlive the session.
+pub struct Lox<'source> {
+    pub interpreter: Interpreter<'source>,
+    sources: Vec<SourceFile<'source>>,
+}
+
+// A source this session has scanned. Each one gets its own range of span offsets, so an error
+// raised by a function defined in an earlier source can still be shown against that source.
+struct SourceFile<'source> {
+    name: String,
+    offset: usize,
+    text: &'source str,
+}
+
+impl SourceFile<'_> {
+    fn contains(&self, span: Span) -> bool {
+        // The end is included, that is where the EOF token of a source sits
+        self.offset <= span.start && span.start <= self.offset + self.text.len()
+    }
+}
+
+#[allow(clippy::needless_lifetimes)]
+impl<'source> Default for Lox<'source> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'source> Lox<'source> {
+    pub fn new() -> Self {
+        Self {
+            interpreter: Interpreter::new(),
+            sources: Vec::new(),
+        }
+    }
+
+    // Runs a whole program
+    pub fn run_source(&mut self, source: &'source str) -> Result<(), LoxError<'source>> {
+        let statements = self.compile(source)?;
+        self.execute(&statements)
+    }
+
+    // Evaluates a single expression such as `fib(10) + 1` and returns its value
+    pub fn eval_expr(&mut self, source: &'source str) -> Result<Value<'source>, LoxError<'source>> {
+        let offset = self.add_source(UNNAMED, source);
+        let tokens = scan(source, offset)?;
+        let expr = Parser::new(tokens)
+            .expression()
+            .map(Rc::new)
+            .map_err(|e| LoxError::Syntax {
+                scanner: Vec::new(),
+                parser: vec![e],
+            })?;
+        self.resolve(&[Stmt::Expression(expr.clone())])?;
+
+        match self.interpreter.evaluate(expr) {
+            Ok(value) => Ok(value),
+            Err(error) => Err(self.runtime_error(error)),
+        }
+    }
+
+    // Scans, parses and resolves `source` against this session without running it
+    pub fn compile(
+        &mut self,
+        source: &'source str,
+    ) -> Result<Vec<Stmt<'source>>, LoxError<'source>> {
+        self.compile_named(UNNAMED, source)
+    }
+
+    // Like `compile`, with the file name diagnostics show for this source
+    pub fn compile_named(
+        &mut self,
+        name: &str,
+        source: &'source str,
+    ) -> Result<Vec<Stmt<'source>>, LoxError<'source>> {
+        self.compile_source(name, source, false)
+    }
+
+    // Like `compile_named`, for a line typed at a prompt: a line made of one expression
+    // without a trailing ';', e.g. `a + b`, becomes an expression statement
+    pub fn compile_line(
+        &mut self,
+        name: &str,
+        source: &'source str,
+    ) -> Result<Vec<Stmt<'source>>, LoxError<'source>> {
+        self.compile_source(name, source, true)
+    }
+
+    fn compile_source(
+        &mut self,
+        name: &str,
+        source: &'source str,
+        allow_expression: bool,
+    ) -> Result<Vec<Stmt<'source>>, LoxError<'source>> {
+        let offset = self.add_source(name, source);
+        let mut scanner = Scanner::with_offset(source, offset);
+        let tokens = scanner.scan_tokens();
+        let scanner_errors = scanner.take_errors();
+
+        // The parser still runs after scanner errors so syntax errors elsewhere are found too
+        let mut parser = Parser::new(tokens.clone());
+        let statements = match parser.parse() {
+            Ok(statements) if scanner_errors.is_empty() => statements,
+            Ok(_) => {
+                return Err(LoxError::Syntax {
+                    scanner: scanner_errors,
+                    parser: Vec::new(),
+                });
+            }
+            Err(parser_errors) => match Parser::new(tokens).expression() {
+                Ok(expr) if allow_expression && scanner_errors.is_empty() => {
+                    vec![Stmt::Expression(Rc::new(expr))]
+                }
+                _ => {
+                    return Err(LoxError::Syntax {
+                        scanner: scanner_errors,
+                        parser: parser_errors,
+                    });
+                }
+            },
+        };
+
+        self.resolve(&statements)?;
+        Ok(statements)
+    }
+
+    // Runs statements produced by `compile`
+    pub fn execute(&mut self, statements: &[Stmt<'source>]) -> Result<(), LoxError<'source>> {
+        match self.interpreter.interpret(statements) {
+            Ok(()) => Ok(()),
+            Err(error) => Err(self.runtime_error(error)),
+        }
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Value<'source>> {
+        Environment::get_at_string(self.interpreter.globals.clone(), 0, name).ok()
+    }
+
+    // Defines the global, or overwrites it if it already exists
+    pub fn set_global(&mut self, name: &str, value: Value<'source>) {
+        self.interpreter
+            .globals
+            .borrow_mut()
+            .define(name.to_string(), value);
+    }
+
+    // Calls a global function or class with the given arguments. The call goes through the
+    // interpreter like one made from Lox, so it shows up in tracebacks and counts towards the
+    // maximum call depth. Errors raised by the call itself, e.g. a failing native, have no
+    // source to point at and become `LoxError::Call`.
+    pub fn call_function(
+        &mut self,
+        name: &str,
+        args: Vec<Value<'source>>,
+    ) -> Result<Value<'source>, LoxError<'source>> {
+        let (arity, callee) = match self.get_global(name) {
+            Some(Value::Callable(function)) => (function.arity(), Value::Callable(function)),
+            Some(Value::Class(class)) => (class.arity(), Value::Class(class)),
+            Some(value) => {
+                return Err(LoxError::Call(format!(
+                    "'{}' is a {}, not a function or class.",
+                    name,
+                    value.type_name()
+                )));
+            }
+            None => return Err(LoxError::Call(format!("Undefined function '{}'.", name))),
+        };
+
+        if args.len() != arity {
+            return Err(LoxError::Call(format!(
+                "'{}' expects {} arguments but got {}.",
+                name,
+                arity,
+                args.len()
+            )));
+        }
+
+        let site = Token::new(
+            TokenType::Identifier,
+            "<host>",
+            None,
+            HOST_LINE,
+            0,
+            HOST_SPAN,
+        );
+        match self.interpreter.call_value(callee, args, &site, site.span) {
+            Ok(value) => Ok(value),
+            Err(error) if error.span() == Some(HOST_SPAN) => {
+                self.interpreter.take_traceback();
+                Err(LoxError::Call(Diagnostic::from(&error).message))
+            }
+            Err(error) => Err(self.runtime_error(error)),
+        }
+    }
+
+    // Writes the diagnostics for `error` to the interpreter's error sink
+    pub fn report(&mut self, error: &LoxError<'source>) {
+        for diagnostic in self.diagnostics(error) {
+            let rendered = self.render(&diagnostic);
+            // There is nowhere left to report a failing error sink to
+            let _ = write!(self.interpreter.error_output, "{}", rendered);
+        }
+    }
+
+    // Like `LoxError::diagnostics`, but a thrown instance is described by its `__str` method,
+    // the same way `print` shows it. If `__str` fails, the plain description is kept.
+    pub fn diagnostics(&mut self, error: &LoxError<'source>) -> Vec<Diagnostic> {
+        let mut diagnostics = error.diagnostics();
+        if let LoxError::Runtime {
+            error: RuntimeError::Thrown { value, .. },
+            ..
+        } = error
+            && let Some(diagnostic) = diagnostics.first_mut()
+        {
+            match self.interpreter.stringify(value) {
+                Ok(text) => diagnostic.message = uncaught_exception(&text),
+                Err(_) => {
+                    self.interpreter.take_traceback();
+                }
+            }
+        }
+        diagnostics
+    }
+
+    // Renders a diagnostic against the source its span points into. Diagnostics without a
+    // span are shown as coming from the latest source.
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        let file = match diagnostic.span {
+            Some(span) => self.sources.iter().find(|file| file.contains(span)),
+            None => self.sources.last(),
+        };
+        match file {
+            Some(file) => {
+                let mut local = diagnostic.clone();
+                local.span = diagnostic
+                    .span
+                    .map(|span| Span::new(span.start - file.offset, span.end - file.offset));
+                local.render(&file.name, file.text)
+            }
+            None => diagnostic.render(UNNAMED, ""),
+        }
+    }
+
+    // Records a source and returns the offset its spans start at
+    fn add_source(&mut self, name: &str, text: &'source str) -> usize {
+        // One past the end of the previous source, so no span is claimed by two sources
+        let offset = self
+            .sources
+            .last()
+            .map_or(0, |last| last.offset + last.text.len() + 1);
+        self.sources.push(SourceFile {
+            name: name.to_string(),
+            offset,
+            text,
+        });
+        offset
+    }
+
+    fn resolve(&mut self, statements: &[Stmt<'source>]) -> Result<(), LoxError<'source>> {
+        let mut resolver = Resolver::new();
+        resolver.resolve_stmts(statements, &mut self.interpreter);
+        let errors = resolver.take_errors();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(LoxError::Resolve(errors))
+        }
+    }
+
+    fn runtime_error(&mut self, error: RuntimeError<'source>) -> LoxError<'source> {
+        LoxError::Runtime {
+            error,
+            traceback: self.interpreter.take_traceback(),
+        }
+    }
+}
+
+fn scan(source: &str, offset: usize) -> Result<Vec<Token<'_>>, LoxError<'_>> {
+    let mut scanner = Scanner::with_offset(source, offset);
+    let tokens = scanner.scan_tokens();
+    let errors = scanner.take_errors();
+    if errors.is_empty() {
+        Ok(tokens)
+    } else {
+        Err(LoxError::Syntax {
+            scanner: errors,
+            parser: Vec::new(),
+        })
+    }
+}
@@ -0,0 +1,5 @@
+// mod.rs
+// Lox module entry point. Re-exports the embedding facade.
+
+pub mod facade;
+pub use facade::*;
@@ -4,16 +4,26 @@
 use std::{
     env, fs,
     io::{self, Read, Write},
+    path::PathBuf,
     process,
 };
 
 use rlox::{
+    ast::stmt::Stmt,
+    diagnostic::Diagnostic,
+    error::{LoxError, ParserError, RuntimeError, ScannerError},
     interpreter::{Interpreter, Value},
+    lox::Lox,
     parser::Parser,
     resolver::Resolver,
-    scanner::Scanner,
+    scanner::{self, Scanner},
+};
+use rustyline::{
+    Context, Editor, Helper, completion::Completer, error::ReadlineError, highlight::Highlighter,
+    hint::Hinter, history::DefaultHistory, validate::Validator,
 };
 use std::fs::File;
+use typed_arena::Arena;
 
 fn main() {
     let mut args = env::args().skip(1);
@@ -25,7 +35,7 @@ fn main() {
                 Some(path) => show_tokens_file(path),
                 None => {
                     eprintln!("Usage: rlox [--]show-tokens <file|->");
-                    process::exit(64);
+                    process::exit(EX_USAGE);
                 }
             }
         }
@@ -40,7 +50,7 @@ fn main() {
             }
             None => {
                 eprintln!("Usage: rlox [--]show-ast <file|-> [output.txt]");
-                process::exit(64);
+                process::exit(EX_USAGE);
             }
         },
         Some(cmd) if cmd == "--show-resolve" || cmd == "show-resolve" => {
@@ -49,7 +59,7 @@ fn main() {
                 Some(path) => show_resolve_file(path),
                 None => {
                     eprintln!("Usage: rlox [--]show-resolve <file|->");
-                    process::exit(64);
+                    process::exit(EX_USAGE);
                 }
             }
         }
@@ -57,194 +67,464 @@ fn main() {
     }
 }
 
+// Exit codes from sysexits.h, the same ones jlox and clox use
+const EX_USAGE: i32 = 64;
+const EX_DATAERR: i32 = 65; // the script failed to scan, parse or resolve
+const EX_SOFTWARE: i32 = 70; // the script failed while running
+const EX_IOERR: i32 = 74;
+
+// How a run of some Lox source ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Success,
+    CompileError,
+    RuntimeError,
+}
+
+impl Outcome {
+    fn exit_code(self) -> i32 {
+        match self {
+            Outcome::Success => 0,
+            Outcome::CompileError => EX_DATAERR,
+            Outcome::RuntimeError => EX_SOFTWARE,
+        }
+    }
+}
+
 fn run_file(path: &str) {
-    let source = fs::read_to_string(path).expect("Could not read file");
-    let mut interpreter = Interpreter::<'_>::new();
-    run(&source, &mut interpreter);
+    let source = read_file(path);
+    let mut lox = Lox::new();
+    let outcome = run(path, &source, &mut lox, false);
+    if outcome != Outcome::Success {
+        process::exit(outcome.exit_code());
+    }
+}
+
+// Reads a whole script, exiting with EX_IOERR if that isn't possible
+fn read_file(path: &str) -> String {
+    fs::read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("Could not read file '{}': {}", path, e);
+        process::exit(EX_IOERR);
+    })
+}
+
+fn read_stdin() -> String {
+    let mut source = String::new();
+    if let Err(e) = io::stdin().read_to_string(&mut source) {
+        eprintln!("Could not read stdin: {}", e);
+        process::exit(EX_IOERR);
+    }
+    source
+}
+
+// File names shown in diagnostics for code that doesn't come from a file
+const REPL_NAME: &str = "<repl>";
+const STDIN_NAME: &str = "<stdin>";
+
+const COMMANDS: &[&str] = &[
+    ":tokens", ":ast", ":env", ":load", ":reset", ":help", ":quit",
+];
+
+// Tab completion for the REPL: meta-commands, keywords and the names of the session's globals.
+struct LoxHelper {
+    keywords: Vec<&'static str>,
+    globals: Vec<String>,
+}
+
+impl Completer for LoxHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        if line.starts_with(':') && !line[..pos].contains(char::is_whitespace) {
+            let candidates = COMMANDS
+                .iter()
+                .filter(|command| command.starts_with(&line[..pos]))
+                .map(|command| command.to_string())
+                .collect();
+            return Ok((0, candidates));
+        }
+
+        let start = line[..pos]
+            .rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .map_or(0, |i| i + 1);
+        let prefix = &line[start..pos];
+        if prefix.is_empty() {
+            return Ok((start, Vec::new()));
+        }
+
+        let mut candidates: Vec<String> = self
+            .keywords
+            .iter()
+            .copied()
+            .chain(self.globals.iter().map(String::as_str))
+            .filter(|word| word.starts_with(prefix))
+            .map(str::to_string)
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for LoxHelper {
+    type Hint = String;
+}
+
+impl Highlighter for LoxHelper {}
+
+impl Validator for LoxHelper {}
+
+impl Helper for LoxHelper {}
+
+fn history_path() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".rlox_history"))
 }
 
 fn run_prompt() {
-    let stdin = io::stdin();
-    let mut line_buf = String::new();
+    let mut editor: Editor<LoxHelper, DefaultHistory> = match Editor::new() {
+        Ok(editor) => editor,
+        Err(e) => {
+            eprintln!("Could not start the line editor: {}", e);
+            process::exit(EX_IOERR);
+        }
+    };
+    editor.set_helper(Some(LoxHelper {
+        keywords: scanner::keywords(),
+        globals: Vec::new(),
+    }));
+
+    let history = history_path();
+    if let Some(path) = &history {
+        // A missing history file just means this is the first session
+        let _ = editor.load_history(path);
+    }
+
+    let mut buffer = String::new();
+
+    // Tokens and AST nodes borrow from the source they were scanned from, and the interpreter
+    // keeps them alive across lines (function bodies, resolved locals, ...). Every line gets
+    // its own owned buffer in the arena so it lives as long as the session does.
+    let sources: Arena<String> = Arena::new();
+    let mut lox = Lox::new();
 
     loop {
-        print!("> ");
-        io::stdout().flush().unwrap();
-        line_buf.clear();
+        if let Some(helper) = editor.helper_mut() {
+            helper.globals = lox
+                .interpreter
+                .globals
+                .borrow()
+                .bindings()
+                .map(|(name, _)| name.clone())
+                .collect();
+        }
 
-        if stdin.read_line(&mut line_buf).unwrap() == 0 {
-            break;
+        let prompt = if buffer.is_empty() { "> " } else { ". " };
+        let line = match editor.readline(prompt) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => {
+                // Ctrl-C throws away the current entry, like most shells do
+                buffer.clear();
+                continue;
+            }
+            Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                eprintln!("Could not read input: {}", e);
+                break;
+            }
+        };
+
+        let input = line.trim();
+        if buffer.is_empty() {
+            if input.is_empty() {
+                continue;
+            }
+            if let Some(command) = input.strip_prefix(':') {
+                let _ = editor.add_history_entry(input);
+                if !run_command(command, &sources, &mut lox) {
+                    break;
+                }
+                continue;
+            }
         }
 
-        let input = line_buf.trim();
-        if input.is_empty() {
+        buffer.push_str(&line);
+        buffer.push('\n');
+        // An empty continuation line submits whatever has been typed so far
+        if !input.is_empty() && is_incomplete(&buffer) {
             continue;
         }
 
-        let mut interpreter = Interpreter::<'_>::new();
-        run(input, &mut interpreter);
+        let source = sources.alloc(std::mem::take(&mut buffer));
+        let _ = editor.add_history_entry(source.trim_end());
+        run(REPL_NAME, source.trim(), &mut lox, true);
+    }
+
+    if let Some(path) = &history
+        && let Err(e) = editor.save_history(path)
+    {
+        eprintln!("Could not save history to {}: {}", path.display(), e);
     }
 }
 
-fn run<'source>(source: &'source str, interpreter: &mut Interpreter<'source>) {
+// Returns true when the input ends in the middle of a string, comment, block, grouping or
+// statement, meaning the REPL should keep reading lines before running it.
+fn is_incomplete(source: &str) -> bool {
     let mut scanner = Scanner::new(source);
-    let tokens = match scanner.scan_tokens() {
-        Ok(tokens) => tokens,
-        Err(e) => {
-            eprintln!("Scanner error: {}", e);
-            return;
-        }
-    };
+    let tokens = scanner.scan_tokens();
+    let errors = scanner.take_errors();
+    if errors.iter().any(|e| {
+        matches!(
+            e,
+            ScannerError::UnterminatedString(..)
+                | ScannerError::UnterminatedEscape(..)
+                | ScannerError::UnterminatedComment(..)
+        )
+    }) {
+        return true;
+    }
+    if !errors.is_empty() {
+        return false;
+    }
 
+    // Only running out of input at the very end means there is more to come; any earlier
+    // error will still be there after the next line is typed
     let mut parser = Parser::new(tokens.clone());
     match parser.parse() {
-        Ok(statements) if !statements.is_empty() => {
-            let mut resolver = Resolver::new();
-            resolver.resolve_stmts(&statements, interpreter);
-
-            let errors = resolver.take_errors();
-            if !errors.is_empty() {
-                for e in errors {
-                    eprintln!("Resolver error: {}", e);
-                }
-                return;
-            }
+        Err(errors) if matches!(errors.last(), Some(ParserError::UnexpectedEof { .. })) => {}
+        _ => return false,
+    }
 
-            if let Err(e) = interpreter.interpret(&statements) {
-                eprintln!("Runtime error: {}", e);
+    // `a + b` is complete on its own even though the statement parser wants a `;`
+    let mut expr_parser = Parser::new(tokens);
+    expr_parser.expr().is_err()
+}
+
+// Handles a `:command` typed at the REPL prompt. Returns false when the session should end.
+fn run_command<'source>(
+    command: &str,
+    sources: &'source Arena<String>,
+    lox: &mut Lox<'source>,
+) -> bool {
+    let (name, arg) = match command.split_once(char::is_whitespace) {
+        Some((name, arg)) => (name, arg.trim()),
+        None => (command, ""),
+    };
+
+    match name {
+        "q" | "quit" | "exit" => return false,
+        "tokens" => show_tokens(REPL_NAME, arg),
+        "ast" => {
+            if let Some(statements) = parse_ast(REPL_NAME, arg) {
+                for stmt in statements {
+                    println!("{:#?}", stmt);
+                }
             }
         }
-        Ok(_) | Err(_) => {
-            if !source.contains(';') {
-                let mut expr_parser = Parser::new(tokens);
-                match expr_parser.expr() {
-                    Ok(expr) => match interpreter.evaluate(expr.into()) {
-                        Ok(value) => {
-                            if !matches!(value, Value::Nil) {
-                                // println!("{}", value);
-                            }
-                        }
-                        Err(e) => eprintln!("Runtime error: {}", e),
-                    },
-                    Err(e) => eprintln!("Parser error: {}", e),
+        "env" => {
+            // Collected first, since `__str` methods may touch the globals while we print
+            let mut bindings: Vec<_> = lox
+                .interpreter
+                .globals
+                .borrow()
+                .bindings()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect();
+            bindings.sort_by(|a, b| a.0.cmp(&b.0));
+            for (name, value) in bindings {
+                match lox.interpreter.stringify(&value) {
+                    Ok(text) => println!("{} = {}", name, text),
+                    Err(_) => {
+                        lox.interpreter.take_traceback();
+                        println!("{} = {}", name, value);
+                    }
                 }
-            } else {
-                eprintln!("Parser error: could not parse input as statement(s)");
             }
         }
+        "load" if !arg.is_empty() => match fs::read_to_string(arg) {
+            Ok(source) => {
+                run(arg, sources.alloc(source), lox, false);
+            }
+            Err(e) => eprintln!("Could not read {}: {}", arg, e),
+        },
+        "load" => eprintln!("Usage: :load <file>"),
+        "reset" => {
+            *lox = Lox::new();
+            println!("Session reset.");
+        }
+        "help" => {
+            println!(":tokens <code>  Print the tokens scanned from <code>");
+            println!(":ast <code>     Print the parsed AST of <code>");
+            println!(":env            List the global variables of this session");
+            println!(":load <file>    Run a file inside this session");
+            println!(":reset          Discard every definition made so far");
+            println!(":quit           Leave the REPL");
+        }
+        _ => eprintln!(
+            "Unknown command ':{}'. Type :help for a list of commands.",
+            command
+        ),
+    }
+    true
+}
+
+fn run<'source>(name: &str, source: &'source str, lox: &mut Lox<'source>, echo: bool) -> Outcome {
+    // Only lines typed at the prompt may be a bare expression, files need their semicolons
+    let compiled = if echo {
+        lox.compile_line(name, source)
+    } else {
+        lox.compile_named(name, source)
+    };
+    let statements = match compiled {
+        Ok(statements) => statements,
+        Err(e) => {
+            lox.report(&e);
+            return Outcome::CompileError;
+        }
+    };
+
+    let result = if echo {
+        statements
+            .iter()
+            .try_for_each(|stmt| echo_statement(stmt, &mut lox.interpreter))
+            .map_err(|error| LoxError::Runtime {
+                error,
+                traceback: lox.interpreter.take_traceback(),
+            })
+    } else {
+        lox.execute(&statements)
+    };
+
+    match result {
+        Ok(()) => Outcome::Success,
+        Err(e) => {
+            lox.report(&e);
+            Outcome::RuntimeError
+        }
+    }
+}
+
+// Prints an error from any phase as a diagnostic pointing into `source`
+fn report<'e, E>(error: &'e E, name: &str, source: &str)
+where
+    Diagnostic: From<&'e E>,
+{
+    eprint!("{}", Diagnostic::from(error).render(name, source));
+}
+
+// Executes a top level REPL statement, printing the value of expression statements.
+// Declarations and `print` run as usual and stay silent.
+fn echo_statement<'source>(
+    stmt: &Stmt<'source>,
+    interpreter: &mut Interpreter<'source>,
+) -> Result<(), RuntimeError<'source>> {
+    match stmt {
+        Stmt::Expression(expr) => {
+            let value = interpreter.evaluate(expr.clone())?;
+            if !matches!(value, Value::Nil) {
+                let text = interpreter.stringify(&value)?;
+                writeln!(interpreter.output, "{}", text).map_err(RuntimeError::Io)?;
+            }
+            Ok(())
+        }
+        _ => interpreter.execute(stmt),
     }
 }
 
 fn show_tokens_file(path: &str) {
-    let source = fs::read_to_string(path).expect("Could not read file");
-    show_tokens(&source);
+    let source = read_file(path);
+    show_tokens(path, &source);
 }
 
 fn show_tokens_stdin() {
-    let mut source = String::new();
-    io::stdin()
-        .read_to_string(&mut source)
-        .expect("Failed to read stdin");
-    show_tokens(&source);
+    let source = read_stdin();
+    show_tokens(STDIN_NAME, &source);
 }
 
-fn show_tokens(source: &str) {
+fn show_tokens(name: &str, source: &str) {
     let mut scanner = Scanner::new(source);
-    match scanner.scan_tokens() {
-        Ok(tokens) => {
-            for token in tokens {
-                println!("{:?}", token);
-            }
-        }
-        Err(e) => {
-            eprintln!("Scanner error: {}", e);
-        }
+    for token in scanner.scan_tokens() {
+        println!("{:?}", token);
+    }
+    for e in scanner.take_errors() {
+        report(&e, name, source);
     }
 }
 
 fn show_ast_file_with_output(path: &str, output: &str) {
-    let source = fs::read_to_string(path).expect("Could not read file");
-    show_ast_to_file(&source, output);
+    let source = read_file(path);
+    show_ast_to_file(path, &source, output);
 }
 
 fn show_ast_stdin_with_output(output: &str) {
-    let mut source = String::new();
-    io::stdin()
-        .read_to_string(&mut source)
-        .expect("Failed to read stdin");
-    show_ast_to_file(&source, output);
+    let source = read_stdin();
+    show_ast_to_file(STDIN_NAME, &source, output);
 }
 
-fn show_ast_to_file(source: &str, output: &str) {
-    let mut scanner = Scanner::new(source);
-    let tokens = match scanner.scan_tokens() {
-        Ok(tokens) => tokens,
-        Err(e) => {
-            eprintln!("Scanner error: {}", e);
-            return;
+fn show_ast_to_file(name: &str, source: &str, output: &str) {
+    if let Some(statements) = parse_ast(name, source) {
+        let mut file = match File::create(output) {
+            Ok(f) => f,
+            Err(e) => {
+                eprintln!("Could not create output file: {}", e);
+                process::exit(EX_IOERR);
+            }
+        };
+        for stmt in statements {
+            writeln!(file, "{:#?}", stmt).unwrap();
         }
-    };
+        println!("AST written to {}", output);
+    }
+}
+
+fn parse_ast<'source>(name: &str, source: &'source str) -> Option<Vec<Stmt<'source>>> {
+    let mut scanner = Scanner::new(source);
+    let tokens = scanner.scan_tokens();
+    let scan_errors = scanner.take_errors();
+    for e in &scan_errors {
+        report(e, name, source);
+    }
+
     let mut parser = Parser::new(tokens);
     match parser.parse() {
-        Ok(statements) => {
-            let mut file = match File::create(output) {
-                Ok(f) => f,
-                Err(e) => {
-                    eprintln!("Could not create output file: {}", e);
-                    return;
-                }
-            };
-            use std::io::Write;
-            for stmt in statements {
-                writeln!(file, "{:#?}", stmt).unwrap();
+        Ok(statements) if scan_errors.is_empty() => Some(statements),
+        Ok(_) => None,
+        Err(errors) => {
+            for e in &errors {
+                report(e, name, source);
             }
-            println!("AST written to {}", output);
-        }
-        Err(e) => {
-            eprintln!("Parser error: {}", e);
+            None
         }
     }
 }
 
 fn show_resolve_file(path: &str) {
-    let source = fs::read_to_string(path).expect("Could not read file");
-    show_resolve(&source);
+    let source = read_file(path);
+    show_resolve(path, &source);
 }
 
 fn show_resolve_stdin() {
-    let mut source = String::new();
-    io::stdin()
-        .read_to_string(&mut source)
-        .expect("Failed to read stdin");
-    show_resolve(&source);
+    let source = read_stdin();
+    show_resolve(STDIN_NAME, &source);
 }
 
-fn show_resolve(source: &str) {
-    let mut scanner = Scanner::new(source);
-    let tokens = match scanner.scan_tokens() {
-        Ok(tokens) => tokens,
-        Err(e) => {
-            eprintln!("Scanner error: {}", e);
-            return;
-        }
+fn show_resolve(name: &str, source: &str) {
+    let Some(statements) = parse_ast(name, source) else {
+        return;
     };
-    let mut parser = Parser::new(tokens);
-    match parser.parse() {
-        Ok(statements) => {
-            let mut interpreter = Interpreter::<'_>::new();
-            let mut resolver = Resolver::new();
-            resolver.resolve_stmts(&statements, &mut interpreter);
-            let errors = resolver.take_errors();
-            if errors.is_empty() {
-                println!("No resolver errors detected.");
-            } else {
-                for error in errors {
-                    println!("Resolver error: {}", error);
-                }
-            }
-        }
-        Err(e) => {
-            eprintln!("Parser error: {}", e);
+    let mut interpreter = Interpreter::<'_>::new();
+    let mut resolver = Resolver::new();
+    resolver.resolve_stmts(&statements, &mut interpreter);
+    let errors = resolver.take_errors();
+    if errors.is_empty() {
+        println!("No resolver errors detected.");
+    } else {
+        for error in errors {
+            print!("{}", Diagnostic::from(&error).render(name, source));
         }
     }
 }
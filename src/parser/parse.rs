@@ -4,10 +4,10 @@
 use crate::{
     ast::{
         expr,
-        stmt::{FunctionDecl, Stmt},
+        stmt::{CatchClause, FieldDecl, FunctionDecl, Stmt},
     },
     error::ParserError,
-    token::{Literal, Token, TokenType},
+    token::{Literal, Span, Token, TokenType},
 };
 use std::rc::Rc;
 
@@ -15,6 +15,7 @@ pub struct Parser<'source> {
     tokens: Vec<Token<'source>>,
     current: usize,
     loop_depth: usize,
+    errors: Vec<ParserError<'source>>, // every error recovered from so far
 }
 
 impl<'source> Parser<'source> {
@@ -23,30 +24,46 @@ impl<'source> Parser<'source> {
             tokens,
             current: 0,
             loop_depth: 0,
+            errors: Vec::new(),
         }
     }
 
-    pub fn parse(&mut self) -> Result<Vec<Stmt<'source>>, ParserError<'source>> {
+    // Parses the whole token stream. Syntax errors don't stop the parser: it records them,
+    // skips to the next statement and keeps going, so one run reports every mistake it can find.
+    pub fn parse(&mut self) -> Result<Vec<Stmt<'source>>, Vec<ParserError<'source>>> {
         let mut statements = Vec::new();
 
         while !self.is_at_end() {
-            if let Some(stmt) = self.declaration()? {
+            if let Some(stmt) = self.declaration() {
                 statements.push(stmt);
             }
         }
 
-        Ok(statements)
+        if self.errors.is_empty() {
+            Ok(statements)
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
     }
 
     pub fn expr(&mut self) -> Result<expr::Expr<'source>, ParserError<'source>> {
         self.comma()
     }
 
-    fn declaration(&mut self) -> Result<Option<Stmt<'source>>, ParserError<'source>> {
+    // Parses the tokens as a single expression that has to span the whole input
+    pub fn expression(&mut self) -> Result<expr::Expr<'source>, ParserError<'source>> {
+        let expr = self.expr()?;
+        self.consume(TokenType::Eof, "Expect end of expression.")?;
+        Ok(expr)
+    }
+
+    fn declaration(&mut self) -> Option<Stmt<'source>> {
         let result = if self.matches(&[TokenType::Var]) {
             self.var_declaration()
         } else if self.matches(&[TokenType::Class]) {
             self.class()
+        } else if self.matches(&[TokenType::Trait]) {
+            self.trait_declaration()
         } else if self.matches(&[TokenType::Fn]) {
             let token = self.previous();
             self.function(token.clone())
@@ -55,34 +72,83 @@ impl<'source> Parser<'source> {
         };
 
         match result {
-            Ok(stmt) => Ok(Some(stmt)),
-            Err(_) => {
+            Ok(stmt) => Some(stmt),
+            Err(e) => {
+                // Tripping over a token the scanner rejected would only repeat its report
+                if !caused_by_scanner(&e) {
+                    self.errors.push(e);
+                }
                 self.synchronize();
-                Ok(None)
+                None
             }
         }
     }
 
     fn class(&mut self) -> Result<Stmt<'source>, ParserError<'source>> {
+        let start = self.previous().span;
         let class_name = self.consume(TokenType::Identifier, "Expect class name.")?;
         let mut superclass: Option<Rc<expr::Expr<'source>>> = None;
         if self.matches(&[TokenType::Less]) {
             let super_name = self.consume(TokenType::Identifier, "Expect superclass name.")?;
             superclass = Some(Rc::new(expr::Expr::Variable { name: super_name }));
         }
+        let mut traits = Vec::new();
+        if self.matches(&[TokenType::With]) {
+            loop {
+                let trait_name = self.consume(TokenType::Identifier, "Expect trait name.")?;
+                traits.push(Rc::new(expr::Expr::Variable { name: trait_name }));
+                if !self.matches(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
         self.consume(TokenType::LeftBrace, "Expect '{' before class body.}")?;
 
         let mut methods: Vec<FunctionDecl<'source>> = Vec::new();
+        let mut static_methods: Vec<FunctionDecl<'source>> = Vec::new();
+        let mut static_fields: Vec<FieldDecl<'source>> = Vec::new();
+        let mut getters: Vec<FunctionDecl<'source>> = Vec::new();
+        let mut setters: Vec<FunctionDecl<'source>> = Vec::new();
         while !self.check(&[TokenType::RightBrace]) && !self.is_at_end() {
+            let is_static = self.matches(&[TokenType::Static]);
+            if is_static && !self.check_next(TokenType::LeftParen) {
+                static_fields.push(self.static_field()?);
+                continue;
+            }
+            if !is_static && self.check_next(TokenType::LeftBrace) {
+                getters.push(self.getter()?);
+                continue;
+            }
+            // `set` only starts a setter when a name follows, so methods can still be called `set`
+            let is_setter = !is_static
+                && self.peek().is_some_and(|token| token.lexeme == "set")
+                && self.check_next(TokenType::Identifier);
+            if is_setter {
+                self.advance();
+            }
             let method_token = Token {
                 kind: TokenType::Identifier,
                 lexeme: "method",
                 literal: None,
                 line: self.current_line(),
+                column: self.peek().map(|token| token.column).unwrap_or(1),
+                span: self.current_span(),
             };
             let method = self.function(method_token)?;
             if let Stmt::Function(func_decl) = method {
-                methods.push(func_decl);
+                if is_setter {
+                    if func_decl.params.len() != 1 {
+                        self.errors.push(ParserError::SetterArity {
+                            line: func_decl.name.as_ref().map_or(0, |name| name.line),
+                            span: func_decl.span,
+                        });
+                    }
+                    setters.push(func_decl);
+                } else if is_static {
+                    static_methods.push(func_decl);
+                } else {
+                    methods.push(func_decl);
+                }
             }
         }
 
@@ -90,13 +156,74 @@ impl<'source> Parser<'source> {
         Ok(Stmt::Class {
             name: class_name,
             superclass,
+            traits,
             methods,
+            static_methods,
+            static_fields,
+            getters,
+            setters,
+            span: self.span_from(start),
         })
     }
 
+    // `name { body }`, a method without a parameter list that runs when the property is read
+    fn getter(&mut self) -> Result<FunctionDecl<'source>, ParserError<'source>> {
+        let name = self.consume(TokenType::Identifier, "Expect getter name.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before getter body.")?;
+        let body = self.function_body()?;
+        Ok(FunctionDecl {
+            span: self.span_from(name.span),
+            name: Some(name),
+            params: Vec::new(),
+            body,
+        })
+    }
+
+    fn trait_declaration(&mut self) -> Result<Stmt<'source>, ParserError<'source>> {
+        let start = self.previous().span;
+        let name = self.consume(TokenType::Identifier, "Expect trait name.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before trait body.")?;
+
+        let mut methods = Vec::new();
+        while !self.check(&[TokenType::RightBrace]) && !self.is_at_end() {
+            let method_token = Token {
+                kind: TokenType::Identifier,
+                lexeme: "method",
+                literal: None,
+                line: self.current_line(),
+                column: self.peek().map(|token| token.column).unwrap_or(1),
+                span: self.current_span(),
+            };
+            if let Stmt::Function(decl) = self.function(method_token)? {
+                methods.push(decl);
+            }
+        }
+
+        self.consume(TokenType::RightBrace, "Expect '}' after trait body.")?;
+        Ok(Stmt::Trait {
+            name,
+            methods,
+            span: self.span_from(start),
+        })
+    }
+
+    // `static name;` or `static name = value;`, after the `static`
+    fn static_field(&mut self) -> Result<FieldDecl<'source>, ParserError<'source>> {
+        let name = self.consume(TokenType::Identifier, "Expect static field name.")?;
+        let initializer = if self.matches(&[TokenType::Equal]) {
+            Some(Rc::new(self.expr()?))
+        } else {
+            None
+        };
+        self.consume(TokenType::Semicolon, "Expect ';' after static field.")?;
+        Ok(FieldDecl { name, initializer })
+    }
+
     fn statement(&mut self) -> Result<Stmt<'source>, ParserError<'source>> {
-        if self.matches(&[TokenType::For]) {
-            self.for_statement()
+        if self.check(&[TokenType::Identifier]) && self.check_next(TokenType::Colon) {
+            self.labeled_statement()
+        } else if self.matches(&[TokenType::For]) {
+            self.for_statement(None)
         } else if self.matches(&[TokenType::If]) {
             self.if_statement()
         } else if self.matches(&[TokenType::Print]) {
@@ -104,20 +231,50 @@ impl<'source> Parser<'source> {
         } else if self.matches(&[TokenType::Return]) {
             self.return_statement()
         } else if self.matches(&[TokenType::While]) {
-            self.while_statement()
+            self.while_statement(None)
+        } else if self.matches(&[TokenType::Try]) {
+            self.try_statement()
+        } else if self.matches(&[TokenType::Throw]) {
+            self.throw_statement()
         } else if self.matches(&[TokenType::Break]) {
             self.break_statement()
+        } else if self.matches(&[TokenType::Continue]) {
+            self.continue_statement()
         } else if self.matches(&[TokenType::LeftBrace]) {
+            let start = self.previous().span;
             let block_stmts = self.block()?;
-            Ok(Stmt::Block(block_stmts))
+            Ok(Stmt::Block(block_stmts, self.span_from(start)))
         } else {
             self.expression_statement()
         }
     }
 
-    fn for_statement(&mut self) -> Result<Stmt<'source>, ParserError<'source>> {
+    // `name: while (...)` or `name: for (...)`, so `break name;` can leave an outer loop
+    fn labeled_statement(&mut self) -> Result<Stmt<'source>, ParserError<'source>> {
+        let label = self.advance();
+        self.advance(); // the ':'
+        if self.matches(&[TokenType::While]) {
+            self.while_statement(Some(label))
+        } else if self.matches(&[TokenType::For]) {
+            self.for_statement(Some(label))
+        } else {
+            Err(ParserError::LabelWithoutLoop {
+                line: label.line,
+                span: label.span,
+            })
+        }
+    }
+
+    fn for_statement(
+        &mut self,
+        label: Option<Token<'source>>,
+    ) -> Result<Stmt<'source>, ParserError<'source>> {
         // 1. Consume the 'for' keyword and expect a left parenthesis
+        let keyword = self.previous().span;
         self.consume(TokenType::LeftParen, "Expected '(' after 'for'.")?;
+        if self.check(&[TokenType::Identifier]) && self.check_next(TokenType::In) {
+            return self.for_in_statement(keyword, label);
+        }
         let initializer: Option<Stmt<'source>> = if self.matches(&[TokenType::Semicolon]) {
             None
         } else if self.matches(&[TokenType::Var]) {
@@ -143,26 +300,100 @@ impl<'source> Parser<'source> {
 
         self.consume(TokenType::RightParen, "Expect ')' after for clauses.")?;
 
-        let mut body = self.statement()?;
-
-        if let Some(inc) = increment {
-            body = Stmt::Block(vec![body, Stmt::Expression(Rc::new(inc))]);
-        }
+        let body = self.loop_body()?;
 
-        let cond = cond.unwrap_or(expr::Expr::Literal(Literal::True));
-        body = Stmt::While {
+        // The desugared nodes all cover the whole `for` statement. The increment stays on the
+        // `while` rather than being appended to the body, so `continue` doesn't skip it.
+        let span = self.span_from(keyword);
+        let cond = cond.unwrap_or(expr::Expr::Literal(Literal::True, keyword));
+        let mut body = Stmt::While {
             condition: Rc::new(cond),
             body: Box::new(body),
+            increment: increment.map(Rc::new),
+            label,
+            span,
         };
 
         if let Some(init) = initializer {
-            body = Stmt::Block(vec![init, body]);
+            body = Stmt::Block(vec![init, body], span);
         }
 
         Ok(body)
     }
 
+    // The rest of `for (name in iterable) body`, after the '('
+    fn for_in_statement(
+        &mut self,
+        keyword: Span,
+        label: Option<Token<'source>>,
+    ) -> Result<Stmt<'source>, ParserError<'source>> {
+        let name = self.advance();
+        self.advance(); // the 'in'
+        let iterable = self.expr()?;
+        self.consume(TokenType::RightParen, "Expect ')' after for-in clause.")?;
+        let body = self.loop_body()?;
+        Ok(Stmt::ForIn {
+            name,
+            iterable: Rc::new(iterable),
+            body: Box::new(body),
+            label,
+            span: self.span_from(keyword),
+        })
+    }
+
+    fn try_statement(&mut self) -> Result<Stmt<'source>, ParserError<'source>> {
+        let start = self.previous().span;
+        self.consume(TokenType::LeftBrace, "Expect '{' after 'try'.")?;
+        let body = self.block()?;
+
+        let catch = if self.matches(&[TokenType::Catch]) {
+            self.consume(TokenType::LeftParen, "Expect '(' after 'catch'.")?;
+            let name = self.consume(TokenType::Identifier, "Expect error variable name.")?;
+            self.consume(TokenType::RightParen, "Expect ')' after error variable.")?;
+            self.consume(TokenType::LeftBrace, "Expect '{' before catch body.")?;
+            Some(CatchClause {
+                name,
+                body: self.block()?,
+            })
+        } else {
+            None
+        };
+
+        let finally = if self.matches(&[TokenType::Finally]) {
+            self.consume(TokenType::LeftBrace, "Expect '{' after 'finally'.")?;
+            Some(self.block()?)
+        } else {
+            None
+        };
+
+        // A bare `try { }` would silently swallow nothing, so one of the clauses is required
+        if catch.is_none() && finally.is_none() {
+            self.consume(
+                TokenType::Catch,
+                "Expect 'catch' or 'finally' after try block.",
+            )?;
+        }
+
+        Ok(Stmt::Try {
+            body,
+            catch,
+            finally,
+            span: self.span_from(start),
+        })
+    }
+
+    fn throw_statement(&mut self) -> Result<Stmt<'source>, ParserError<'source>> {
+        let keyword = self.previous().clone();
+        let value = self.expr()?;
+        self.consume(TokenType::Semicolon, "Expect ';' after thrown value.")?;
+        Ok(Stmt::Throw {
+            keyword,
+            value: Rc::new(value),
+        })
+    }
+
     fn if_statement(&mut self) -> Result<Stmt<'source>, ParserError<'source>> {
+        let start = self.previous().span;
         self.consume(TokenType::LeftParen, "Expect '(' after 'if'.")?;
         let cond = self.expr()?;
         self.consume(TokenType::RightParen, "Expect ')' after condition.")?;
@@ -177,16 +408,23 @@ impl<'source> Parser<'source> {
             condition: Rc::new(cond),
             then_branch: Box::new(then_br),
             else_branch: else_br,
+            span: self.span_from(start),
         })
     }
 
     fn print_statement(&mut self) -> Result<Stmt<'source>, ParserError<'source>> {
+        let keyword = self.previous().clone();
         let value = self.expr()?;
         self.consume(TokenType::Semicolon, "Expect ';' after value.")?;
-        Ok(Stmt::Print(Rc::new(value)))
+        Ok(Stmt::Print {
+            span: self.span_from(keyword.span),
+            keyword,
+            value: Rc::new(value),
+        })
     }
 
     fn var_declaration(&mut self) -> Result<Stmt<'source>, ParserError<'source>> {
+        let start = self.previous().span;
         let value = self.consume(TokenType::Identifier, "Expected variable name.")?;
 
         let mut init: Option<Rc<expr::Expr<'source>>> = None;
@@ -198,6 +436,7 @@ impl<'source> Parser<'source> {
         Ok(Stmt::Var {
             name: value,
             initializer: init,
+            span: self.span_from(start),
         })
     }
 
@@ -217,28 +456,77 @@ impl<'source> Parser<'source> {
         })
     }
 
-    fn while_statement(&mut self) -> Result<Stmt<'source>, ParserError<'source>> {
-        self.loop_depth += 1;
+    fn while_statement(
+        &mut self,
+        label: Option<Token<'source>>,
+    ) -> Result<Stmt<'source>, ParserError<'source>> {
+        let start = self.previous().span;
         self.consume(TokenType::LeftParen, "Expected '(' after 'while'.")?;
         let cond = self.expr()?;
         self.consume(TokenType::RightParen, "Expected ')' after condition.")?;
-        let cond_body = self.statement()?;
-        self.loop_depth -= 1;
+        let cond_body = self.loop_body()?;
         Ok(Stmt::While {
             condition: Rc::new(cond),
             body: Box::new(cond_body),
+            increment: None,
+            label,
+            span: self.span_from(start),
         })
     }
 
+    // The body of a `while` or `for`, where `break` and `continue` are allowed
+    fn loop_body(&mut self) -> Result<Stmt<'source>, ParserError<'source>> {
+        self.loop_depth += 1;
+        let body = self.statement();
+        self.loop_depth -= 1;
+        body
+    }
+
+    // A function's body is not inside the loops around its declaration
+    fn function_body(&mut self) -> Result<Vec<Stmt<'source>>, ParserError<'source>> {
+        let enclosing_depth = std::mem::replace(&mut self.loop_depth, 0);
+        let body = self.block();
+        self.loop_depth = enclosing_depth;
+        body
+    }
+
     fn break_statement(&mut self) -> Result<Stmt<'source>, ParserError<'source>> {
+        let kword = self.previous().clone();
         if self.loop_depth == 0 {
             return Err(ParserError::BreakException {
-                line: self.current_line(),
+                line: kword.line,
+                span: kword.span,
             });
         }
-        let kword = self.previous().clone();
+        let label = self.loop_label();
         self.consume(TokenType::Semicolon, "Expected ';' after keyword.")?;
-        Ok(Stmt::Break { keyword: kword })
+        Ok(Stmt::Break {
+            keyword: kword,
+            label,
+        })
+    }
+
+    fn continue_statement(&mut self) -> Result<Stmt<'source>, ParserError<'source>> {
+        let keyword = self.previous().clone();
+        if self.loop_depth == 0 {
+            return Err(ParserError::ContinueException {
+                line: keyword.line,
+                span: keyword.span,
+            });
+        }
+        let label = self.loop_label();
+        self.consume(TokenType::Semicolon, "Expected ';' after keyword.")?;
+        Ok(Stmt::Continue { keyword, label })
+    }
+
+    // The optional label after `break` or `continue`. Whether a loop by that name
+    // encloses the statement is checked by the resolver.
+    fn loop_label(&mut self) -> Option<Token<'source>> {
+        if self.matches(&[TokenType::Identifier]) {
+            Some(self.previous().clone())
+        } else {
+            None
+        }
     }
 
     fn expression_statement(&mut self) -> Result<Stmt<'source>, ParserError<'source>> {
@@ -249,6 +537,12 @@ impl<'source> Parser<'source> {
 
     fn function(&mut self, kind: Token<'source>) -> Result<Stmt<'source>, ParserError<'source>> {
         let name = self.consume(TokenType::Identifier, &format!("Expect {} name.", kind))?;
+        // Functions start at their `fn` keyword, methods at their name
+        let start = if kind.kind == TokenType::Fn {
+            kind.span
+        } else {
+            name.span
+        };
         self.consume(
             TokenType::LeftParen,
             &format!("Expect '(' after {} name.", kind),
@@ -257,8 +551,13 @@ impl<'source> Parser<'source> {
         let mut parameters = Vec::new();
         if !self.check(&[TokenType::RightParen]) {
             loop {
-                if parameters.len() >= 255 {
-                    eprintln!("Can't have more than 255 parameters.");
+                if parameters.len() == 255 {
+                    // Reported once, and not worth bailing out over, the parser is still in a
+                    // sane state
+                    self.errors.push(ParserError::TooManyParams {
+                        line: self.current_line(),
+                        span: self.current_span(),
+                    });
                 }
                 parameters.push(self.consume(TokenType::Identifier, "Expect parameter name.")?);
 
@@ -275,12 +574,13 @@ impl<'source> Parser<'source> {
             TokenType::LeftBrace,
             &format!("Expect '{{' before {} name.", kind),
         )?;
-        let body = self.block()?;
+        let body = self.function_body()?;
 
         let decl = FunctionDecl {
             name: Some(name),
             params: parameters,
             body,
+            span: self.span_from(start),
         };
         Ok(Stmt::Function(decl))
     }
@@ -288,7 +588,7 @@ impl<'source> Parser<'source> {
     fn block(&mut self) -> Result<Vec<Stmt<'source>>, ParserError<'source>> {
         let mut statements: Vec<Stmt<'source>> = Vec::new();
         while !self.check(&[TokenType::RightBrace]) && !self.is_at_end() {
-            if let Some(stmt) = self.declaration()? {
+            if let Some(stmt) = self.declaration() {
                 statements.push(stmt);
             }
         }
@@ -318,34 +618,46 @@ impl<'source> Parser<'source> {
         let expr = self.or()?;
 
         // Is the next token '='?
-        if let Some(token) = self.peek() {
-            if token.kind == TokenType::Equal {
-                self.advance(); // consume it 
-                // Recursively call assignment to get the value - We are now on the right-hand side
-                // of the assignment
-                let value = self.assignment()?;
-
-                match expr {
-                    expr::Expr::Variable { name } => {
-                        return Ok(expr::Expr::Assign {
-                            name,
-                            value: Rc::new(value),
-                        });
-                    }
-                    expr::Expr::Get { object, name } => {
-                        return Ok(expr::Expr::Set {
-                            object,
-                            name,
-                            value: Rc::new(value),
-                        });
-                    }
-                    _ => {
-                        let token = self.previous();
-                        return Err(ParserError::InvalidAssignmentTarget {
-                            found: token.clone(),
-                            line: token.line,
-                        });
-                    }
+        if let Some(token) = self.peek()
+            && token.kind == TokenType::Equal
+        {
+            self.advance(); // consume it 
+            // Recursively call assignment to get the value - We are now on the right-hand side
+            // of the assignment
+            let value = self.assignment()?;
+
+            match expr {
+                expr::Expr::Variable { name } => {
+                    return Ok(expr::Expr::Assign {
+                        name,
+                        value: Rc::new(value),
+                    });
+                }
+                expr::Expr::Get { object, name } => {
+                    return Ok(expr::Expr::Set {
+                        object,
+                        name,
+                        value: Rc::new(value),
+                    });
+                }
+                expr::Expr::Index {
+                    object,
+                    index,
+                    bracket,
+                } => {
+                    return Ok(expr::Expr::IndexSet {
+                        object,
+                        index,
+                        bracket,
+                        value: Rc::new(value),
+                    });
+                }
+                _ => {
+                    let token = self.previous();
+                    return Err(ParserError::InvalidAssignmentTarget {
+                        found: token.clone(),
+                        line: token.line,
+                    });
                 }
             }
         }
@@ -386,29 +698,30 @@ impl<'source> Parser<'source> {
     fn ternary(&mut self) -> Result<expr::Expr<'source>, ParserError<'source>> {
         let expr = self.equality()?;
 
-        if let Some(token) = self.peek().cloned() {
-            if token.kind == TokenType::Question {
-                self.advance();
-                let true_expr = self.assignment()?;
+        if let Some(token) = self.peek().cloned()
+            && token.kind == TokenType::Question
+        {
+            self.advance();
+            let true_expr = self.assignment()?;
 
-                if let Some(colon_token) = self.peek() {
-                    if colon_token.kind == TokenType::Colon {
-                        self.advance();
-                        let false_expr = self.ternary()?;
-                        return Ok(expr::Expr::ternary(expr, true_expr, false_expr));
-                    } else {
-                        return Err(ParserError::UnexpectedToken {
-                            expected: TokenType::Colon,
-                            found: colon_token.clone(),
-                            line: token.line,
-                        });
-                    }
+            if let Some(colon_token) = self.peek() {
+                if colon_token.kind == TokenType::Colon {
+                    self.advance();
+                    let false_expr = self.ternary()?;
+                    return Ok(expr::Expr::ternary(expr, true_expr, false_expr));
                 } else {
-                    return Err(ParserError::UnexpectedEof {
-                        expected: "':'".to_string(),
-                        line: self.current_line(),
+                    return Err(ParserError::UnexpectedToken {
+                        expected: TokenType::Colon,
+                        found: colon_token.clone(),
+                        line: token.line,
                     });
                 }
+            } else {
+                return Err(ParserError::UnexpectedEof {
+                    expected: "':'".to_string(),
+                    line: self.current_line(),
+                    span: self.current_span(),
+                });
             }
         }
         Ok(expr)
@@ -508,8 +821,11 @@ impl<'source> Parser<'source> {
 
         if !self.check(&[TokenType::RightParen]) {
             loop {
-                if arguments.len() >= 255 {
-                    eprintln!("Can't have more than 255 arguments.");
+                if arguments.len() == 255 {
+                    self.errors.push(ParserError::TooManyArgs {
+                        line: self.current_line(),
+                        span: self.current_span(),
+                    });
                 }
                 arguments.push(Rc::new(self.assignment()?));
 
@@ -545,6 +861,17 @@ impl<'source> Parser<'source> {
                         name,
                     }
                 }
+                Some(TokenType::LeftBracket) => {
+                    self.advance();
+                    let index = self.assignment()?;
+                    let bracket =
+                        self.consume(TokenType::RightBracket, "Expect ']' after index.")?;
+                    expr = expr::Expr::Index {
+                        object: Rc::new(expr),
+                        index: Rc::new(index),
+                        bracket,
+                    }
+                }
                 Some(TokenType::Increment | TokenType::Decrement) => {
                     let operator = self.advance().clone();
                     expr = expr::Expr::mutate(operator, expr, true); // postfix = true
@@ -562,13 +889,16 @@ impl<'source> Parser<'source> {
             .ok_or_else(|| ParserError::UnexpectedEof {
                 expected: "expression".to_string(),
                 line: self.current_line(),
+                span: self.current_span(),
             })?
             .clone();
 
         match token.kind {
             TokenType::Super => {
                 let keyword = self.advance().clone();
-                let method = self.consume(TokenType::Dot, "Expect '.' after 'super'.")?;
+                self.consume(TokenType::Dot, "Expect '.' after 'super'.")?;
+                let method =
+                    self.consume(TokenType::Identifier, "Expect superclass method name.")?;
                 Ok(expr::Expr::Super { keyword, method })
             }
             TokenType::This => {
@@ -581,15 +911,15 @@ impl<'source> Parser<'source> {
             }
             TokenType::False => {
                 self.advance();
-                Ok(expr::Expr::literal(Literal::False))
+                Ok(expr::Expr::literal(Literal::False, token.span))
             }
             TokenType::True => {
                 self.advance();
-                Ok(expr::Expr::literal(Literal::True))
+                Ok(expr::Expr::literal(Literal::True, token.span))
             }
             TokenType::Nil => {
                 self.advance();
-                Ok(expr::Expr::literal(Literal::Nil))
+                Ok(expr::Expr::literal(Literal::Nil, token.span))
             }
             TokenType::Number | TokenType::String => {
                 let token = self.advance();
@@ -597,7 +927,7 @@ impl<'source> Parser<'source> {
                     .literal
                     .clone()
                     .expect("Literal token missing literal value");
-                Ok(expr::Expr::literal(literal))
+                Ok(expr::Expr::literal(literal, token.span))
             }
             TokenType::LeftParen => {
                 self.advance();
@@ -605,10 +935,55 @@ impl<'source> Parser<'source> {
                 let next = self.peek();
                 if next.unwrap().kind == TokenType::RightParen {
                     self.advance();
-                    Ok(expr::Expr::grouping(expr))
+                    Ok(expr::Expr::grouping(expr, self.span_from(token.span)))
+                } else if self.is_at_end() {
+                    Err(ParserError::UnexpectedEof {
+                        expected: "')'".to_string(),
+                        line: self.current_line(),
+                        span: self.current_span(),
+                    })
                 } else {
-                    Err(ParserError::UnterminatedParen { line: token.line })
+                    Err(ParserError::UnterminatedParen {
+                        line: token.line,
+                        span: token.span,
+                    })
+                }
+            }
+            TokenType::LeftBracket => {
+                self.advance();
+                let mut elements = Vec::new();
+                // A trailing comma is allowed so long literals can be written one element per line
+                while !self.check(&[TokenType::RightBracket]) {
+                    elements.push(Rc::new(self.assignment()?));
+                    if !self.matches(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
+                self.consume(TokenType::RightBracket, "Expect ']' after list elements.")?;
+                Ok(expr::Expr::List {
+                    elements,
+                    span: self.span_from(token.span),
+                })
+            }
+            // Only reached in expression position, a '{' starting a statement is a block
+            TokenType::LeftBrace => {
+                let brace = self.advance();
+                let mut entries = Vec::new();
+                while !self.check(&[TokenType::RightBrace]) {
+                    let key = self.assignment()?;
+                    self.consume(TokenType::Colon, "Expect ':' after map key.")?;
+                    let value = self.assignment()?;
+                    entries.push((Rc::new(key), Rc::new(value)));
+                    if !self.matches(&[TokenType::Comma]) {
+                        break;
+                    }
                 }
+                self.consume(TokenType::RightBrace, "Expect '}' after map entries.")?;
+                Ok(expr::Expr::Map {
+                    brace,
+                    entries,
+                    span: self.span_from(token.span),
+                })
             }
             TokenType::Fn => {
                 self.advance();
@@ -617,9 +992,10 @@ impl<'source> Parser<'source> {
                 let mut parameters = Vec::new();
                 if !self.check(&[TokenType::RightParen]) {
                     loop {
-                        if parameters.len() >= 255 {
-                            return Err(ParserError::TooManyParams {
+                        if parameters.len() == 255 {
+                            self.errors.push(ParserError::TooManyParams {
                                 line: self.current_line(),
+                                span: self.current_span(),
                             });
                         }
 
@@ -634,12 +1010,18 @@ impl<'source> Parser<'source> {
                 }
                 self.consume(TokenType::RightParen, "Expect ')' after parameters.")?;
                 self.consume(TokenType::LeftBrace, "Expect '{' before lambda body")?;
-                let body_block = self.block()?;
+                let body_block = self.function_body()?;
                 Ok(expr::Expr::Lambda {
                     params: parameters,
                     body: body_block,
+                    span: self.span_from(token.span),
                 })
             }
+            TokenType::Eof => Err(ParserError::UnexpectedEof {
+                expected: "expression".to_string(),
+                line: token.line,
+                span: token.span,
+            }),
             _ => Err(ParserError::UnexpectedExpression {
                 found: token.clone(),
                 line: token.line,
@@ -658,13 +1040,16 @@ impl<'source> Parser<'source> {
             match self.peek() {
                 Some(token) => match token.kind {
                     TokenType::Class
+                    | TokenType::Trait
                     | TokenType::Fn
                     | TokenType::Var
                     | TokenType::For
                     | TokenType::If
                     | TokenType::While
                     | TokenType::Print
-                    | TokenType::Return => return,
+                    | TokenType::Return
+                    | TokenType::Try
+                    | TokenType::Throw => return,
                     _ => {
                         self.advance();
                     }
@@ -681,6 +1066,11 @@ impl<'source> Parser<'source> {
     ) -> Result<Token<'source>, ParserError<'source>> {
         match self.peek() {
             Some(token) if token.kind == expected => Ok(self.advance()),
+            Some(token) if token.kind == TokenType::Eof => Err(ParserError::UnexpectedEof {
+                expected: message.to_string(),
+                line: token.line,
+                span: token.span,
+            }),
             Some(token) => Err(ParserError::UnexpectedToken {
                 expected,
                 found: token.clone(),
@@ -689,6 +1079,7 @@ impl<'source> Parser<'source> {
             None => Err(ParserError::UnexpectedEof {
                 expected: message.to_string(),
                 line: self.current_line(),
+                span: self.current_span(),
             }),
         }
     }
@@ -700,7 +1091,13 @@ impl<'source> Parser<'source> {
         self.previous().clone()
     }
 
-    fn is_at_end(&self) -> bool {
+    fn check_next(&self, kind: TokenType) -> bool {
+        self.tokens
+            .get(self.current + 1)
+            .is_some_and(|token| token.kind == kind)
+    }
+
+    pub fn is_at_end(&self) -> bool {
         matches!(self.peek(), Some(token) if token.kind == TokenType::Eof)
     }
 
@@ -709,10 +1106,10 @@ impl<'source> Parser<'source> {
     }
 
     fn check(&self, matches: &[TokenType]) -> bool {
-        if let Some(token) = self.peek() {
-            if matches.contains(&token.kind) {
-                return true;
-            }
+        if let Some(token) = self.peek()
+            && matches.contains(&token.kind)
+        {
+            return true;
         }
         false
     }
@@ -725,13 +1122,31 @@ impl<'source> Parser<'source> {
         self.peek().map(|token| token.line).unwrap_or(1)
     }
 
+    fn current_span(&self) -> Span {
+        self.peek().map(|token| token.span).unwrap_or_default()
+    }
+
+    // Span from `start` up to and including the last consumed token
+    fn span_from(&self, start: Span) -> Span {
+        start.to(self.previous().span)
+    }
+
     fn matches(&mut self, types: &[TokenType]) -> bool {
-        if let Some(token) = self.peek() {
-            if types.contains(&token.kind) {
-                self.advance();
-                return true;
-            }
+        if let Some(token) = self.peek()
+            && types.contains(&token.kind)
+        {
+            self.advance();
+            return true;
         }
         false
     }
 }
+
+fn caused_by_scanner(error: &ParserError) -> bool {
+    match error {
+        ParserError::UnexpectedExpression { found, .. }
+        | ParserError::UnexpectedToken { found, .. }
+        | ParserError::InvalidAssignmentTarget { found, .. } => found.kind == TokenType::Error,
+        _ => false,
+    }
+}
@@ -5,11 +5,7 @@ use std::collections::HashMap;
 
 use crate::ast::expr::Expr;
 use crate::token::Token;
-use crate::{
-    ast::stmt::{FunctionDecl, Stmt},
-    error::CompilerError,
-    interpreter::Interpreter,
-};
+use crate::{ast::stmt::Stmt, error::CompilerError, interpreter::Interpreter};
 use std::rc::Rc;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -25,6 +21,7 @@ enum ClassType {
     None,
     Class,
     SubClass,
+    Trait,
 }
 
 pub struct Resolver<'source> {
@@ -32,6 +29,7 @@ pub struct Resolver<'source> {
     errors: Vec<CompilerError<'source>>,
     current_function: FunctionType,
     current_class: ClassType,
+    labels: Vec<&'source str>, // labels of the loops enclosing the current statement
 }
 
 #[allow(clippy::needless_lifetimes)]
@@ -48,6 +46,7 @@ impl<'source> Resolver<'source> {
             errors: Vec::new(),
             current_function: FunctionType::None,
             current_class: ClassType::None,
+            labels: Vec::new(),
         }
     }
 
@@ -63,12 +62,14 @@ impl<'source> Resolver<'source> {
 
     fn resolve_stmt(&mut self, stmt: &Stmt<'source>, interpreter: &mut Interpreter<'source>) {
         match stmt {
-            Stmt::Block(stmts) => {
+            Stmt::Block(stmts, _) => {
                 self.begin_scope();
                 self.resolve_stmts(stmts, interpreter);
                 self.end_scope();
             }
-            Stmt::Var { name, initializer } => {
+            Stmt::Var {
+                name, initializer, ..
+            } => {
                 self.declare(name);
                 if let Some(expr) = initializer {
                     self.resolve_expr(expr, interpreter);
@@ -80,12 +81,23 @@ impl<'source> Resolver<'source> {
                     self.declare(name);
                     self.define(name);
                 }
-                self.resolve_function(func, interpreter, FunctionType::Function);
+                self.resolve_function(
+                    &func.params,
+                    &func.body,
+                    interpreter,
+                    FunctionType::Function,
+                );
             }
             Stmt::Class {
                 name,
                 superclass,
+                traits,
                 methods,
+                static_methods,
+                static_fields,
+                getters,
+                setters,
+                ..
             } => {
                 let enclosing_class = self.current_class;
                 self.current_class = ClassType::Class;
@@ -93,20 +105,22 @@ impl<'source> Resolver<'source> {
                 self.declare(name);
                 self.define(name);
 
-                if let Some(super_expr) = &superclass {
-                    if let Expr::Variable { name: super_name } = &**super_expr {
-                        if super_name.lexeme == name.lexeme {
-                            self.errors.push(CompilerError::SelfInheritance {
-                                line: super_name.line,
-                            });
-                        }
-                    }
+                if let Some(super_expr) = &superclass
+                    && let Expr::Variable { name: super_name } = &**super_expr
+                    && super_name.lexeme == name.lexeme
+                {
+                    self.errors.push(CompilerError::SelfInheritance {
+                        name: super_name.clone(),
+                    });
                 }
 
                 if let Some(superclass_expr) = &superclass {
                     self.current_class = ClassType::SubClass;
                     self.resolve_expr(superclass_expr, interpreter);
                 }
+                for trait_expr in traits {
+                    self.resolve_expr(trait_expr, interpreter);
+                }
 
                 if let Some(_superclass) = &superclass {
                     self.begin_scope();
@@ -128,7 +142,17 @@ impl<'source> Resolver<'source> {
                         } else {
                             FunctionType::Method
                         };
-                    self.resolve_function(method, interpreter, declaration);
+                    self.resolve_function(&method.params, &method.body, interpreter, declaration);
+                }
+                // Accessors are methods that the interpreter calls for `obj.name` and
+                // `obj.name = value`, and static methods see the class itself as `this`
+                for method in getters.iter().chain(setters).chain(static_methods) {
+                    self.resolve_function(
+                        &method.params,
+                        &method.body,
+                        interpreter,
+                        FunctionType::Method,
+                    );
                 }
                 self.end_scope();
 
@@ -137,12 +161,53 @@ impl<'source> Resolver<'source> {
                 }
 
                 self.current_class = enclosing_class;
+
+                // Initializers run in the scope the class is declared in, once the class exists
+                for field in static_fields {
+                    if let Some(initializer) = &field.initializer {
+                        self.resolve_expr(initializer, interpreter);
+                    }
+                }
+            }
+            Stmt::Trait { name, methods, .. } => {
+                let enclosing_class = self.current_class;
+                self.current_class = ClassType::Trait;
+
+                self.declare(name);
+                self.define(name);
+
+                // Trait methods get `super` from whichever class mixes them in
+                self.begin_scope();
+                self.scopes
+                    .last_mut()
+                    .unwrap()
+                    .insert("super".to_string(), true);
+                self.begin_scope();
+                self.scopes
+                    .last_mut()
+                    .unwrap()
+                    .insert("this".to_string(), true);
+
+                for method in methods {
+                    let declaration =
+                        if method.name.as_ref().map(|name| name.lexeme) == Some("init") {
+                            FunctionType::Initializer
+                        } else {
+                            FunctionType::Method
+                        };
+                    self.resolve_function(&method.params, &method.body, interpreter, declaration);
+                }
+                self.end_scope();
+                self.end_scope();
+
+                self.current_class = enclosing_class;
             }
             Stmt::Expression(expr) => self.resolve_expr(expr, interpreter),
             Stmt::If {
                 condition,
                 then_branch,
                 else_branch,
+                ..
             } => {
                 self.resolve_expr(condition, interpreter);
                 self.resolve_stmt(then_branch, interpreter);
@@ -150,7 +215,7 @@ impl<'source> Resolver<'source> {
                     self.resolve_stmt(else_branch_stmt, interpreter);
                 }
             }
-            Stmt::Print(expr) => self.resolve_expr(expr, interpreter),
+            Stmt::Print { value, .. } => self.resolve_expr(value, interpreter),
             Stmt::Return { keyword, value } => {
                 if self.current_function == FunctionType::None {
                     self.errors.push(CompilerError::IllegalReturn {
@@ -166,9 +231,79 @@ impl<'source> Resolver<'source> {
                     self.resolve_expr(value, interpreter);
                 }
             }
-            Stmt::While { condition, body } => {
+            Stmt::While {
+                condition,
+                body,
+                increment,
+                label,
+                ..
+            } => {
                 self.resolve_expr(condition, interpreter);
+                if let Some(label) = label {
+                    self.labels.push(label.lexeme);
+                }
+                self.resolve_stmt(body, interpreter);
+                if label.is_some() {
+                    self.labels.pop();
+                }
+                if let Some(increment) = increment {
+                    self.resolve_expr(increment, interpreter);
+                }
+            }
+            Stmt::ForIn {
+                name,
+                iterable,
+                body,
+                label,
+                ..
+            } => {
+                self.resolve_expr(iterable, interpreter);
+                // The loop variable lives in its own scope around the body, as the interpreter
+                // defines it in a fresh environment for every element
+                self.begin_scope();
+                self.declare(name);
+                self.define(name);
+                if let Some(label) = label {
+                    self.labels.push(label.lexeme);
+                }
                 self.resolve_stmt(body, interpreter);
+                if label.is_some() {
+                    self.labels.pop();
+                }
+                self.end_scope();
+            }
+            Stmt::Try {
+                body,
+                catch,
+                finally,
+                ..
+            } => {
+                self.begin_scope();
+                self.resolve_stmts(body, interpreter);
+                self.end_scope();
+                if let Some(catch) = catch {
+                    self.begin_scope();
+                    self.declare(&catch.name);
+                    self.define(&catch.name);
+                    self.resolve_stmts(&catch.body, interpreter);
+                    self.end_scope();
+                }
+                if let Some(finally) = finally {
+                    self.begin_scope();
+                    self.resolve_stmts(finally, interpreter);
+                    self.end_scope();
+                }
+            }
+            Stmt::Throw { value, .. } => self.resolve_expr(value, interpreter),
+            Stmt::Break {
+                label: Some(label), ..
+            }
+            | Stmt::Continue {
+                label: Some(label), ..
+            } if !self.labels.contains(&label.lexeme) => {
+                self.errors.push(CompilerError::UndefinedLabel {
+                    label: label.clone(),
+                });
             }
             _ => {}
         }
@@ -177,13 +312,12 @@ impl<'source> Resolver<'source> {
     fn resolve_expr(&mut self, expr: &Rc<Expr<'source>>, interpreter: &mut Interpreter<'source>) {
         match &**expr {
             Expr::Variable { name } => {
-                if !self.scopes.is_empty() {
-                    if let Some(scope) = self.scopes.last() {
-                        if let Some(false) = scope.get(name.lexeme) {
-                            self.errors
-                                .push(CompilerError::LocalVarDecl { name: name.clone() });
-                        }
-                    }
+                if !self.scopes.is_empty()
+                    && let Some(scope) = self.scopes.last()
+                    && let Some(false) = scope.get(name.lexeme)
+                {
+                    self.errors
+                        .push(CompilerError::LocalVarDecl { name: name.clone() });
                 }
                 self.resolve_local(expr.clone(), name, interpreter);
             }
@@ -230,12 +364,12 @@ impl<'source> Resolver<'source> {
                 if self.current_class == ClassType::None {
                     self.errors.push(CompilerError::SuperTypeError {
                         msg: "Can't use 'super' outside of a class.".to_string(),
-                        line: keyword.line,
+                        keyword: keyword.clone(),
                     })
-                } else if self.current_class != ClassType::SubClass {
+                } else if self.current_class == ClassType::Class {
                     self.errors.push(CompilerError::SuperTypeError {
                         msg: "Can't use 'super' in a class with no superclass.".to_string(),
-                        line: keyword.line,
+                        keyword: keyword.clone(),
                     })
                 }
                 self.resolve_local(expr.clone(), keyword, interpreter);
@@ -251,7 +385,7 @@ impl<'source> Resolver<'source> {
                 }
                 self.resolve_local(expr.clone(), keyword, interpreter);
             }
-            Expr::Grouping(expr) => self.resolve_expr(expr, interpreter),
+            Expr::Grouping(expr, _) => self.resolve_expr(expr, interpreter),
             Expr::Logical {
                 left,
                 operator: _,
@@ -261,8 +395,36 @@ impl<'source> Resolver<'source> {
                 self.resolve_expr(right, interpreter);
             }
             Expr::Unary { operator: _, right } => self.resolve_expr(right, interpreter),
-            Expr::Literal(_) => {}
-            _ => unimplemented!(),
+            Expr::Mutate { operand, .. } => self.resolve_expr(operand, interpreter),
+            Expr::Lambda { params, body, .. } => {
+                self.resolve_function(params, body, interpreter, FunctionType::Function);
+            }
+            Expr::Literal(..) => {}
+            Expr::List { elements, .. } => {
+                for element in elements {
+                    self.resolve_expr(element, interpreter);
+                }
+            }
+            Expr::Map { entries, .. } => {
+                for (key, value) in entries {
+                    self.resolve_expr(key, interpreter);
+                    self.resolve_expr(value, interpreter);
+                }
+            }
+            Expr::Index { object, index, .. } => {
+                self.resolve_expr(object, interpreter);
+                self.resolve_expr(index, interpreter);
+            }
+            Expr::IndexSet {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                self.resolve_expr(object, interpreter);
+                self.resolve_expr(index, interpreter);
+                self.resolve_expr(value, interpreter);
+            }
         }
     }
 
@@ -283,20 +445,24 @@ impl<'source> Resolver<'source> {
 
     fn resolve_function(
         &mut self,
-        function: &FunctionDecl<'source>,
+        params: &[Token<'source>],
+        body: &[Stmt<'source>],
         interpreter: &mut Interpreter<'source>,
         func_type: FunctionType,
     ) {
         let enclosing_func = self.current_function;
         self.current_function = func_type;
+        // Loops around a function's declaration can't be left from inside its body
+        let enclosing_labels = std::mem::take(&mut self.labels);
 
         self.begin_scope();
-        for param in &function.params {
+        for param in params {
             self.declare(param);
             self.define(param);
         }
-        self.resolve_stmts(&function.body, interpreter);
+        self.resolve_stmts(body, interpreter);
         self.end_scope();
+        self.labels = enclosing_labels;
         self.current_function = enclosing_func;
     }
 
@@ -312,7 +478,7 @@ impl<'source> Resolver<'source> {
         if let Some(scope) = self.scopes.last_mut() {
             if scope.contains_key(name.lexeme) {
                 self.errors
-                    .push(CompilerError::ExistingVar { line: name.line })
+                    .push(CompilerError::ExistingVar { name: name.clone() })
             }
             scope.insert(name.lexeme.to_string(), false);
         }
@@ -6,7 +6,7 @@ use std::{collections::HashMap, sync::RwLock};
 
 use crate::{
     error::ScannerError,
-    token::{Literal, Token, TokenType},
+    token::{Literal, Span, Token, TokenType},
 };
 
 // Scanner struct to hold the state of the Scanner
@@ -18,6 +18,11 @@ pub struct Scanner<'source> {
     start: usize,
     current: usize,
     line: usize,
+    line_start: usize, // byte offset at which the current line begins
+    start_line: usize, // line and column of the token currently being scanned
+    start_column: usize,
+    offset: usize, // added to every span, see `with_offset`
+    errors: Vec<ScannerError>,
 }
 
 // This is our "static initializer" for keywords.
@@ -41,30 +46,78 @@ static KEYWORDS: Lazy<RwLock<HashMap<&'static str, TokenType>>> = Lazy::new(|| {
     m.insert("var", TokenType::Var);
     m.insert("while", TokenType::While);
     m.insert("break", TokenType::Break);
+    m.insert("continue", TokenType::Continue);
+    m.insert("in", TokenType::In);
+    m.insert("try", TokenType::Try);
+    m.insert("catch", TokenType::Catch);
+    m.insert("finally", TokenType::Finally);
+    m.insert("throw", TokenType::Throw);
+    m.insert("static", TokenType::Static);
+    m.insert("trait", TokenType::Trait);
+    m.insert("with", TokenType::With);
     RwLock::new(m)
 });
 
+// Every reserved word of the language, e.g. for completion in the REPL.
+pub fn keywords() -> Vec<&'static str> {
+    KEYWORDS
+        .read()
+        .expect("Failed to read keywords")
+        .keys()
+        .copied()
+        .collect()
+}
+
 impl<'source> Scanner<'source> {
     pub fn new(source: &'source str) -> Self {
+        Self::with_offset(source, 0)
+    }
+
+    // A scanner whose spans start at `offset` instead of 0, for sessions that scan several
+    // sources and need to tell afterwards which one a span points into
+    pub fn with_offset(source: &'source str, offset: usize) -> Self {
         Self {
             source,
             tokens: Vec::new(),
             start: 0, // &str is byte indexed
             current: 0,
             line: 1, // but lines always start at 1
+            line_start: 0,
+            start_line: 1,
+            start_column: 1,
+            offset,
+            errors: Vec::new(),
         }
     }
     // Scans the source code and returns a vector of tokens.
-    pub fn scan_tokens(&mut self) -> Result<Vec<Token<'source>>, ScannerError> {
+    // Lexical errors don't stop the scan: the offending lexeme becomes an `Error` token and
+    // the error is kept for `take_errors`, so every problem in the source is found in one pass.
+    pub fn scan_tokens(&mut self) -> Vec<Token<'source>> {
         while !self.is_at_end() {
             self.start = self.current;
-            self.scan_token()?;
+            self.start_line = self.line;
+            self.start_column = self.column_at(self.start);
+            if let Err(e) = self.scan_token() {
+                self.add_token(TokenType::Error);
+                self.errors.push(e);
+            }
         }
 
         // Add the EOF token at the end of the tokens vector
-        self.tokens
-            .push(Token::new(TokenType::Eof, "", None, self.line));
-        Ok(std::mem::take(&mut self.tokens))
+        let end = self.source.len();
+        self.tokens.push(Token::new(
+            TokenType::Eof,
+            "",
+            None,
+            self.line,
+            self.column_at(end),
+            Span::new(self.offset + end, self.offset + end),
+        ));
+        std::mem::take(&mut self.tokens)
+    }
+
+    pub fn take_errors(self) -> Vec<ScannerError> {
+        self.errors
     }
 
     fn scan_token(&mut self) -> Result<(), ScannerError> {
@@ -75,6 +128,8 @@ impl<'source> Scanner<'source> {
             Some(')') => self.add_token(TokenType::RightParen),
             Some('{') => self.add_token(TokenType::LeftBrace),
             Some('}') => self.add_token(TokenType::RightBrace),
+            Some('[') => self.add_token(TokenType::LeftBracket),
+            Some(']') => self.add_token(TokenType::RightBracket),
             Some(',') => self.add_token(TokenType::Comma),
             Some('.') => self.add_token(TokenType::Dot),
             Some('-') => {
@@ -148,7 +203,7 @@ impl<'source> Scanner<'source> {
             Some(' ') => {}
             Some('\r') => {}
             Some('\t') => {}
-            Some('\n') => self.line += 1,
+            Some('\n') => self.newline(),
             Some('"') => self.string()?,
             Some(c) => {
                 if self.is_digit(c) {
@@ -156,7 +211,7 @@ impl<'source> Scanner<'source> {
                 } else if self.is_alpha(c) {
                     self.identifier();
                 } else {
-                    return Err(ScannerError::UnexpectedChar(c, self.line));
+                    return Err(ScannerError::UnexpectedChar(c, self.line, self.span()));
                 };
             }
             None => {}
@@ -216,15 +271,15 @@ impl<'source> Scanner<'source> {
             match ch {
                 '"' => break, // End of string
                 '\n' => {
-                    self.line += 1;
                     value.push('\n'); // Add actual newline to string
                     self.advance();
+                    self.newline();
                 }
                 '\\' => {
                     // Escape sequence
                     self.advance(); // consume the '\'
                     if self.is_at_end() {
-                        return Err(ScannerError::UnterminatedEscape(self.line));
+                        return Err(ScannerError::UnterminatedEscape(self.line, self.span()));
                     }
 
                     // Process the escape character
@@ -255,7 +310,9 @@ impl<'source> Scanner<'source> {
                             value.push(c);
                             self.advance();
                         }
-                        None => return Err(ScannerError::UnterminatedEscape(self.line)),
+                        None => {
+                            return Err(ScannerError::UnterminatedEscape(self.line, self.span()));
+                        }
                     }
                 }
                 c => {
@@ -267,7 +324,7 @@ impl<'source> Scanner<'source> {
 
         // If we reach here, we either found a closing quote or reached the end of the source
         if self.is_at_end() {
-            return Err(ScannerError::UnterminatedString(self.line));
+            return Err(ScannerError::UnterminatedString(self.line, self.span()));
         }
         // We found the closing quote, so we consume it
         self.advance();
@@ -314,7 +371,7 @@ impl<'source> Scanner<'source> {
     }
 
     fn is_digit(&mut self, c: char) -> bool {
-        return c >= '0' && c <= '9';
+        c.is_ascii_digit()
     }
 
     fn advance(&mut self) -> Option<char> {
@@ -335,13 +392,16 @@ impl<'source> Scanner<'source> {
                     self.advance(); // lone '*', keep scanning
                 }
                 Some('\n') => {
-                    self.line += 1; // increment line counter for newlines in comments
                     self.advance();
+                    self.newline(); // keep line counting correct inside comments
                 }
                 Some(_) => {
                     self.advance(); // any other char
                 }
-                None => return Err(ScannerError::UnterminatedComment(self.line)), // reached EOF
+                None => {
+                    // reached EOF
+                    return Err(ScannerError::UnterminatedComment(self.line, self.span()));
+                }
             }
         }
     }
@@ -357,8 +417,25 @@ impl<'source> Scanner<'source> {
             kind: token_type,
             lexeme: text,
             literal,
-            line: self.line,
+            line: self.start_line,
+            column: self.start_column,
+            span: self.span(),
         };
         self.tokens.push(token);
     }
+
+    // Called right after a '\n' has been consumed
+    fn newline(&mut self) {
+        self.line += 1;
+        self.line_start = self.current;
+    }
+
+    fn column_at(&self, offset: usize) -> usize {
+        self.source[self.line_start..offset].chars().count() + 1
+    }
+
+    // Span of the lexeme scanned so far
+    fn span(&self) -> Span {
+        Span::new(self.offset + self.start, self.offset + self.current)
+    }
 }
@@ -11,6 +11,8 @@ pub struct Token<'source> {
     pub lexeme: &'source str, // Just want to borrow a slice, no ownership needed for now
     pub literal: Option<Literal>,
     pub line: usize,
+    pub column: usize, // 1-based, counted in chars from the start of the line
+    pub span: Span,
 }
 
 impl<'source> Token<'source> {
@@ -19,12 +21,38 @@ impl<'source> Token<'source> {
         lexeme: &'source str,
         literal: Option<Literal>,
         line: usize,
+        column: usize,
+        span: Span,
     ) -> Self {
         Self {
             kind,
             lexeme,
             literal,
             line,
+            column,
+            span,
+        }
+    }
+}
+
+// Byte range of a piece of source code, `start` inclusive and `end` exclusive.
+// Line and column can always be recovered from the source, so only the offsets are stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    // Smallest span covering both `self` and `other`
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
         }
     }
 }
@@ -46,6 +74,8 @@ pub enum TokenType {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
     Dot,
     Minus,
@@ -81,6 +111,7 @@ pub enum TokenType {
     Fn,
     For,
     If,
+    In,
     Nil,
     Or,
     Print,
@@ -91,7 +122,16 @@ pub enum TokenType {
     Var,
     While,
     Break,
+    Continue,
+    Try,
+    Catch,
+    Finally,
+    Throw,
+    Static,
+    Trait,
+    With,
 
+    Error, // a lexeme the scanner rejected, kept so the parser can step over it
     Eof,
 }
 
@@ -122,6 +162,8 @@ impl fmt::Display for TokenType {
             TokenType::RightParen => ")",
             TokenType::LeftBrace => "{",
             TokenType::RightBrace => "}",
+            TokenType::LeftBracket => "[",
+            TokenType::RightBracket => "]",
             TokenType::Comma => ",",
             TokenType::Dot => ".",
             TokenType::Minus => "-",
@@ -157,6 +199,7 @@ impl fmt::Display for TokenType {
             TokenType::Fn => "fn",
             TokenType::For => "for",
             TokenType::If => "if",
+            TokenType::In => "in",
             TokenType::Nil => "nil",
             TokenType::Or => "or",
             TokenType::Print => "print",
@@ -167,6 +210,15 @@ impl fmt::Display for TokenType {
             TokenType::Var => "var",
             TokenType::While => "while",
             TokenType::Break => "break",
+            TokenType::Continue => "continue",
+            TokenType::Try => "try",
+            TokenType::Catch => "catch",
+            TokenType::Finally => "finally",
+            TokenType::Throw => "throw",
+            TokenType::Static => "static",
+            TokenType::Trait => "trait",
+            TokenType::With => "with",
+            TokenType::Error => "ERROR",
             TokenType::Eof => "EOF",
         };
         write!(f, "{}", token_str)
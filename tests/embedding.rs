@@ -0,0 +1,396 @@
+// embedding.rs
+// Exercises the `Lox` facade the way a Rust host uses it, without going through .lox files.
+
+use std::error::Error;
+
+use rlox::{
+    callable::{bool_arg, number_arg, string_arg},
+    diagnostic::Diagnostic,
+    error::LoxError,
+    interpreter::{SharedBuffer, Value},
+    lox::Lox,
+};
+
+// A session whose error sink is captured, so reports can be checked
+fn session<'source>() -> (Lox<'source>, SharedBuffer) {
+    let errors = SharedBuffer::new();
+    let mut lox = Lox::new();
+    lox.interpreter.error_output = Box::new(errors.clone());
+    (lox, errors)
+}
+
+#[test]
+fn errors_are_rendered_against_the_source_they_come_from() {
+    let (mut lox, errors) = session();
+    lox.compile_named("first.lox", "fn f() {\n  print 1 - \"a\";\n}")
+        .and_then(|statements| lox.execute(&statements))
+        .unwrap();
+
+    let error = lox
+        .compile_named("second.lox", "f();")
+        .and_then(|statements| lox.execute(&statements))
+        .unwrap_err();
+    lox.report(&error);
+
+    let report = errors.take();
+    assert!(report.contains("--> first.lox:2:9"), "{}", report);
+    assert!(report.contains("2 |   print 1 - \"a\";"), "{}", report);
+}
+
+// The examples from the Embedding section of README.md, kept in sync with it, so they are known
+// to compile and to use `?` into `Box<dyn Error>`
+#[test]
+fn readme_embedding_example() -> Result<(), Box<dyn Error>> {
+    let mut lox = Lox::new();
+    lox.run_source("fn add(a, b) { return a + b; } var total = add(1, 2);")?;
+
+    assert_eq!(lox.get_global("total"), Some(Value::Number(3.0)));
+    lox.set_global("k", Value::Number(10.0));
+    let sum = lox.eval_expr("add(k, total)")?; // 13
+    let five = lox.call_function("add", vec![Value::Number(1.0), Value::Number(4.0)])?;
+    assert_eq!(sum, Value::Number(13.0));
+    assert_eq!(five, Value::Number(5.0));
+
+    let out = SharedBuffer::new();
+    lox.interpreter.output = Box::new(out.clone());
+    lox.run_source("print 1 + 2;")?;
+    assert_eq!(out.contents(), "3\n");
+
+    lox.interpreter.define_native("sqrt", 1, |_, args| {
+        let n = number_arg(&args, 0)?; // "sqrt: Argument 1 must be a number, got string."
+        if n < 0.0 {
+            return Err("can't take the root of a negative number".into());
+        }
+        Ok(Value::Number(n.sqrt()))
+    });
+    let root = lox.eval_expr("sqrt(16)")?; // 4
+    assert_eq!(root, Value::Number(4.0));
+
+    let error = lox.eval_expr("sqrt(\"a\")").unwrap_err();
+    assert_eq!(
+        error.diagnostics()[0].message,
+        "sqrt: Argument 1 must be a number, got string."
+    );
+    let error = lox.eval_expr("sqrt(-1)").unwrap_err();
+    assert_eq!(
+        error.diagnostics()[0].message,
+        "sqrt: can't take the root of a negative number"
+    );
+    Ok(())
+}
+
+#[test]
+fn host_calls_show_up_in_tracebacks() {
+    let mut lox = Lox::new();
+    lox.run_source("fn inner() { return nil - 1; }\nfn outer() { return inner(); }")
+        .unwrap();
+
+    let error = lox.call_function("outer", Vec::new()).unwrap_err();
+    let diagnostic = &error.diagnostics()[0];
+    assert_eq!(diagnostic.message, "Operands of '-' must be numbers.");
+    assert_eq!(
+        diagnostic.notes,
+        [
+            "in inner(), called at line 2",
+            "in outer(), called from the host"
+        ]
+    );
+}
+
+#[test]
+fn host_calls_count_towards_the_call_depth() {
+    let mut lox = Lox::new();
+    lox.interpreter.max_call_depth = 1;
+    lox.run_source("fn f() { return g(); } fn g() { return 1; }")
+        .unwrap();
+
+    let error = lox.call_function("f", Vec::new()).unwrap_err();
+    assert_eq!(error.diagnostics()[0].message, "Stack overflow.");
+}
+
+#[test]
+fn failing_natives_called_by_the_host_name_themselves() {
+    let mut lox = Lox::new();
+    lox.interpreter
+        .define_native("fail", 0, |_, _| Err("no luck".into()));
+
+    match lox.call_function("fail", Vec::new()) {
+        Err(LoxError::Call(message)) => assert_eq!(message, "fail: no luck"),
+        other => panic!("expected a call error, got {:?}", other),
+    }
+}
+
+// A session with a few natives written against the argument helpers
+fn with_natives<'source>() -> Lox<'source> {
+    let mut lox = Lox::new();
+    lox.interpreter.define_native("repeat", 2, |_, args| {
+        let text = string_arg(&args, 0)?;
+        let times = number_arg(&args, 1)?;
+        Ok(Value::String(text.repeat(times as usize)))
+    });
+    lox.interpreter.define_native("either", 3, |_, args| {
+        let pick_first = bool_arg(&args, 0)?;
+        Ok(args[if pick_first { 1 } else { 2 }].clone())
+    });
+    lox.interpreter.define_native("root", 1, |_, args| {
+        let n = number_arg(&args, 0)?;
+        if n < 0.0 {
+            return Err("can't take the root of a negative number".into());
+        }
+        Ok(Value::Number(n.sqrt()))
+    });
+    lox
+}
+
+// The runtime error message and line a script stops with
+fn runtime_error(lox: &mut Lox<'static>, source: &'static str) -> (String, Option<usize>) {
+    match lox.run_source(source).unwrap_err() {
+        LoxError::Runtime { error, .. } => (Diagnostic::from(&error).message, error.line()),
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+}
+
+#[test]
+fn natives_receive_their_arguments() {
+    let mut lox = with_natives();
+    lox.run_source("var a = repeat(\"ab\", 3); var b = either(false, 1, 2); var c = root(16);")
+        .unwrap();
+    assert_eq!(
+        lox.get_global("a"),
+        Some(Value::String("ababab".to_string()))
+    );
+    assert_eq!(lox.get_global("b"), Some(Value::Number(2.0)));
+    assert_eq!(lox.get_global("c"), Some(Value::Number(4.0)));
+}
+
+#[test]
+fn native_argument_errors_name_the_native_and_argument() {
+    let mut lox = with_natives();
+    let (message, line) = runtime_error(&mut lox, "var ok = 1;\nrepeat(\"ab\", \"3\");");
+    assert_eq!(message, "repeat: Argument 2 must be a number, got string.");
+    assert_eq!(line, Some(2));
+
+    let (message, _) = runtime_error(&mut lox, "either(nil, 1, 2);");
+    assert_eq!(message, "either: Argument 1 must be a boolean, got nil.");
+}
+
+#[test]
+fn native_errors_are_reported_at_the_call_site() {
+    let mut lox = with_natives();
+    let (message, line) = runtime_error(&mut lox, "\n\nroot(-1);");
+    assert_eq!(message, "root: can't take the root of a negative number");
+    assert_eq!(line, Some(3));
+}
+
+#[test]
+fn native_arity_is_checked_before_the_closure_runs() {
+    let mut lox = with_natives();
+    let (message, _) = runtime_error(&mut lox, "root(1, 2);");
+    assert_eq!(message, "Expected 1 arguments but got 2.");
+}
+
+#[test]
+fn native_errors_can_be_caught() {
+    let mut lox = with_natives();
+    let out = SharedBuffer::new();
+    lox.interpreter.output = Box::new(out.clone());
+    lox.run_source("try { root(-4); } catch (e) { print e.message; }")
+        .unwrap();
+    assert_eq!(
+        out.contents(),
+        "root: can't take the root of a negative number\n"
+    );
+}
+
+#[test]
+fn eval_expr_sees_the_session_globals() {
+    let mut lox = Lox::new();
+    lox.run_source("var base = 40; fn plus(n) { return base + n; }")
+        .unwrap();
+    assert_eq!(lox.eval_expr("plus(2)").unwrap(), Value::Number(42.0));
+    assert_eq!(
+        lox.eval_expr("\"a\" + \"b\"").unwrap(),
+        Value::String("ab".to_string())
+    );
+}
+
+#[test]
+fn eval_expr_reports_syntax_and_runtime_errors() {
+    let mut lox = Lox::new();
+    let syntax = lox.eval_expr("1 +").unwrap_err();
+    assert!(syntax.is_compile_error());
+
+    let runtime = lox.eval_expr("nil - 1").unwrap_err();
+    assert!(!runtime.is_compile_error());
+    assert_eq!(
+        runtime.diagnostics()[0].message,
+        "Operands of '-' must be numbers."
+    );
+}
+
+#[test]
+fn globals_can_be_read_and_written_by_the_host() {
+    let mut lox = Lox::new();
+    assert_eq!(lox.get_global("missing"), None);
+
+    lox.set_global("limit", Value::Number(3.0));
+    lox.run_source("var doubled = limit * 2;").unwrap();
+    assert_eq!(lox.get_global("doubled"), Some(Value::Number(6.0)));
+
+    // Setting an existing global overwrites it
+    lox.set_global("limit", Value::Bool(true));
+    assert_eq!(lox.get_global("limit"), Some(Value::Bool(true)));
+}
+
+#[test]
+fn call_function_calls_functions_and_classes() {
+    let mut lox = Lox::new();
+    lox.run_source("fn add(a, b) { return a + b; } class Point { init(x) { this.x = x; } }")
+        .unwrap();
+
+    let sum = lox
+        .call_function("add", vec![Value::Number(1.0), Value::Number(2.0)])
+        .unwrap();
+    assert_eq!(sum, Value::Number(3.0));
+
+    let point = lox
+        .call_function("Point", vec![Value::Number(5.0)])
+        .unwrap();
+    lox.set_global("p", point);
+    assert_eq!(lox.eval_expr("p.x").unwrap(), Value::Number(5.0));
+}
+
+#[test]
+fn call_function_rejects_bad_calls() {
+    let mut lox = Lox::new();
+    lox.run_source("fn one(a) { return a; } var number = 1;")
+        .unwrap();
+
+    let message = |error: LoxError| match error {
+        LoxError::Call(message) => message,
+        other => panic!("expected a call error, got {:?}", other),
+    };
+    assert_eq!(
+        message(lox.call_function("nope", Vec::new()).unwrap_err()),
+        "Undefined function 'nope'."
+    );
+    assert_eq!(
+        message(lox.call_function("number", Vec::new()).unwrap_err()),
+        "'number' is a number, not a function or class."
+    );
+    assert_eq!(
+        message(lox.call_function("one", Vec::new()).unwrap_err()),
+        "'one' expects 1 arguments but got 0."
+    );
+}
+
+#[test]
+fn output_and_errors_go_to_their_own_sinks() {
+    let (mut lox, errors) = session();
+    let output = SharedBuffer::new();
+    lox.interpreter.output = Box::new(output.clone());
+
+    let error = lox
+        .run_source("print \"before\";\nprint nil - 1;")
+        .unwrap_err();
+    lox.report(&error);
+
+    assert_eq!(output.contents(), "before\n");
+    assert_eq!(
+        errors.contents(),
+        "Runtime error: Operands of '-' must be numbers.\n \
+         --> <script>:2:7\n  \
+         |\n\
+         2 | print nil - 1;\n  \
+         |       ^^^^^^^\n"
+    );
+}
+
+#[test]
+fn shared_buffers_can_be_drained() {
+    let (mut lox, errors) = session();
+    let error = lox.run_source("var;").unwrap_err();
+    lox.report(&error);
+
+    assert!(errors.take().starts_with("Parser error:"));
+    assert_eq!(errors.contents(), "");
+}
+
+#[test]
+fn values_containing_themselves_can_be_displayed() {
+    let mut lox = Lox::new();
+    lox.run_source("var xs = [1]; push(xs, xs); var m = {}; m[1] = m;")
+        .unwrap();
+    assert_eq!(lox.get_global("xs").unwrap().to_string(), "[1, [...]]");
+    assert_eq!(lox.get_global("m").unwrap().to_string(), "{1: {...}}");
+}
+
+// The golden tests ignore notes, so tracebacks are checked here
+#[test]
+fn a_catch_inside_finally_keeps_the_pending_traceback() {
+    let mut lox = Lox::new();
+    let error = lox
+        .run_source(
+            "fn inner() { return nil - 1; }
+             fn outer() {
+               try { inner(); } finally {
+                 try { throw \"ignored\"; } catch (e) {}
+               }
+             }
+             outer();",
+        )
+        .unwrap_err();
+    assert_eq!(
+        error.diagnostics()[0].notes,
+        [
+            "in inner(), called at line 3",
+            "in outer(), called at line 7"
+        ]
+    );
+}
+
+#[test]
+fn a_failing_class_declaration_leaves_the_session_usable() {
+    let mut lox = Lox::new();
+    let error = lox
+        .run_source("class A {} trait T { m() {} } trait U { m() {} } class B < A with T, U {}")
+        .unwrap_err();
+    assert_eq!(
+        error.diagnostics()[0].message,
+        "Method 'm' is defined by both traits T and U."
+    );
+
+    lox.run_source("var y = 2;").unwrap();
+    assert_eq!(lox.eval_expr("y").unwrap(), Value::Number(2.0));
+}
+
+#[test]
+fn uncaught_instances_are_described_by_their_str_method() {
+    let mut lox = Lox::new();
+    let error = lox
+        .run_source("class E { __str() { return \"E!\"; } } throw E();")
+        .unwrap_err();
+    assert_eq!(lox.diagnostics(&error)[0].message, "Uncaught exception: E!");
+
+    // A failing `__str` falls back to the plain description and leaves no traceback behind
+    let error = lox
+        .run_source("class Bad { __str() { return 1; } } throw Bad();")
+        .unwrap_err();
+    assert_eq!(
+        lox.diagnostics(&error)[0].message,
+        "Uncaught exception: Bad instance"
+    );
+    let error = lox.run_source("print nil - 1;").unwrap_err();
+    assert!(error.diagnostics()[0].notes.is_empty());
+}
+
+#[test]
+fn only_prompt_lines_may_leave_out_the_semicolon() {
+    let mut lox = Lox::new();
+    let error = lox.run_source("1 + 2").unwrap_err();
+    assert!(error.is_compile_error());
+    assert!(lox.compile_named("script.lox", "1 + 2").is_err());
+
+    let statements = lox.compile_line("<repl>", "1 + 2").unwrap();
+    assert_eq!(statements.len(), 1);
+}
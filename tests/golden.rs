@@ -0,0 +1,161 @@
+// golden.rs
+// Runs every .lox file under examples/ and tests/lox/ and checks what it prints against the
+// annotations in its comments, in the style of the Crafting Interpreters test suite:
+//
+//   print 1 + 2;   // expect: 3
+//   print nil - 1; // expect runtime error: Operands of '-' must be numbers.
+//   var a = a;     // expect error: Can't read local variable in its own initializer.
+//
+// Output lines must appear in the order of their annotations. Errors must be reported on the
+// line their annotation is on, and a script stops at its runtime error.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use rlox::{interpreter::SharedBuffer, lox::Lox, token::Span};
+
+const EXPECT_OUTPUT: &str = "// expect:";
+const EXPECT_RUNTIME_ERROR: &str = "// expect runtime error:";
+const EXPECT_ERROR: &str = "// expect error:";
+
+// An error as the test cares about it: the line it was reported on and its message
+type Reported = (usize, String);
+
+#[derive(Debug, Default, PartialEq)]
+struct Expectations {
+    output: Vec<String>,
+    errors: Vec<Reported>,
+    runtime_error: Option<Reported>,
+}
+
+impl Expectations {
+    fn parse(source: &str) -> Self {
+        let mut expected = Self::default();
+        for (index, line) in source.lines().enumerate() {
+            let line_number = index + 1;
+            if let Some(text) = annotation(line, EXPECT_OUTPUT) {
+                expected.output.push(text);
+            } else if let Some(message) = annotation(line, EXPECT_RUNTIME_ERROR) {
+                expected.runtime_error = Some((line_number, message));
+            } else if let Some(message) = annotation(line, EXPECT_ERROR) {
+                expected.errors.push((line_number, message));
+            }
+        }
+        expected
+    }
+}
+
+fn annotation(line: &str, marker: &str) -> Option<String> {
+    let rest = &line[line.find(marker)? + marker.len()..];
+    Some(rest.strip_prefix(' ').unwrap_or(rest).to_string())
+}
+
+// Runs the script and collects the same three things its annotations describe
+fn run(source: &str) -> Expectations {
+    let output = SharedBuffer::new();
+    let mut lox = Lox::new();
+    lox.interpreter.output = Box::new(output.clone());
+
+    let mut actual = Expectations::default();
+    if let Err(error) = lox.run_source(source) {
+        let reported = lox
+            .diagnostics(&error)
+            .into_iter()
+            .map(|d| (line_of(source, d.span), d.message));
+        if error.is_compile_error() {
+            actual.errors = reported.collect();
+            actual.errors.sort();
+        } else {
+            actual.runtime_error = reported.into_iter().next();
+        }
+    }
+    actual.output = output.contents().lines().map(String::from).collect();
+    actual
+}
+
+fn line_of(source: &str, span: Option<Span>) -> usize {
+    span.map_or(0, |span| source[..span.start].matches('\n').count() + 1)
+}
+
+fn check(path: &Path) -> Result<(), String> {
+    let source = fs::read_to_string(path).map_err(|e| format!("could not read file: {}", e))?;
+    let mut expected = Expectations::parse(&source);
+    expected.errors.sort();
+    let actual = run(&source);
+
+    let mut problems = Vec::new();
+    if actual.output != expected.output {
+        problems.push(format!(
+            "output differs\n  expected: {:?}\n  actual:   {:?}",
+            expected.output, actual.output
+        ));
+    }
+    if actual.errors != expected.errors {
+        problems.push(format!(
+            "compile errors differ\n  expected: {:?}\n  actual:   {:?}",
+            expected.errors, actual.errors
+        ));
+    }
+    if actual.runtime_error != expected.runtime_error {
+        problems.push(format!(
+            "runtime error differs\n  expected: {:?}\n  actual:   {:?}",
+            expected.runtime_error, actual.runtime_error
+        ));
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems.join("\n"))
+    }
+}
+
+fn lox_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let entries = fs::read_dir(dir).unwrap_or_else(|e| panic!("{}: {}", dir.display(), e));
+    for entry in entries {
+        let path = entry.expect("unreadable directory entry").path();
+        if path.is_dir() {
+            lox_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "lox") {
+            files.push(path);
+        }
+    }
+}
+
+fn check_dir(dir: &str) {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut files = Vec::new();
+    lox_files(&root.join(dir), &mut files);
+    files.sort();
+    assert!(!files.is_empty(), "no .lox files found in {}", dir);
+
+    let failures: Vec<String> = files
+        .iter()
+        .filter_map(|path| {
+            check(path).err().map(|problem| {
+                let name = path.strip_prefix(root).unwrap_or(path);
+                format!("{}: {}", name.display(), problem)
+            })
+        })
+        .collect();
+
+    assert!(
+        failures.is_empty(),
+        "{} of {} scripts failed:\n\n{}",
+        failures.len(),
+        files.len(),
+        failures.join("\n\n")
+    );
+}
+
+#[test]
+fn examples() {
+    check_dir("examples");
+}
+
+#[test]
+fn suite() {
+    check_dir("tests/lox");
+}